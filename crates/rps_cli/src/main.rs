@@ -0,0 +1,609 @@
+//! Play `rps` games and manage `blp` deposits from the terminal. Salts for
+//! every move you commit to are kept in an encrypted vault until the game is
+//! over, and any game waiting on one of your reveals is revealed on startup.
+
+use std::path::PathBuf;
+
+use anchor_lang::{AccountDeserialize, Discriminator};
+use anyhow::{anyhow, bail, Context, Result};
+use blp::Pool;
+use clap::{ArgEnum, Parser, Subcommand};
+use rps::logic::{CommitMode, CommitmentVersion, GameConfig, GameState, MoveSet, PlayerState, RPS};
+use rps::{Config, Game, PlayerInfo};
+use rps_client::commitment;
+use rps_client::rps_token::TokenMint;
+use rps_client::vault::{Entry, Vault};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+
+#[derive(Parser)]
+#[clap(name = "rps-cli", version, about)]
+struct Cli {
+    #[clap(long, default_value = "http://localhost:8899")]
+    url: String,
+
+    /// Defaults to the solana cli's keypair
+    #[clap(long)]
+    keypair: Option<PathBuf>,
+
+    /// Where salts are kept until their games are over. The passphrase is
+    /// read from RPS_VAULT_PASSPHRASE or prompted for
+    #[clap(long)]
+    vault: Option<PathBuf>,
+
+    /// Don't reveal games waiting on you before running the command
+    #[clap(long)]
+    no_auto_reveal: bool,
+
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create your player info, needed once before playing
+    Init,
+    /// Create a game and commit to your first move
+    Create {
+        #[clap(long)]
+        wager: u64,
+        #[clap(long, arg_enum)]
+        choice: Choice,
+        /// Random if not set
+        #[clap(long)]
+        seed: Option<u64>,
+        /// Makes the game private, only joinable with the same secret
+        #[clap(long)]
+        secret: Option<u64>,
+        #[clap(long, default_value = "600")]
+        challenge_duration: u64,
+        #[clap(long, default_value = "600")]
+        reveal_duration: u64,
+        /// Player 2 commits too instead of playing in the open
+        #[clap(long)]
+        both_commit: bool,
+        /// Rock, paper, scissors, lizard, spock
+        #[clap(long)]
+        rpsls: bool,
+        /// Use the old u64 salt commitments, for players on older clients.
+        /// Only works while the program allows them
+        #[clap(long)]
+        v1_commitment: bool,
+    },
+    /// Join an open game
+    Join {
+        seed: u64,
+        #[clap(long, arg_enum)]
+        choice: Choice,
+        #[clap(long)]
+        secret: Option<u64>,
+    },
+    /// Reveal the move you committed to
+    Reveal { seed: u64 },
+    /// Expire a game the other player stopped playing
+    Expire { seed: u64 },
+    /// Pay out a finished game
+    Settle {
+        seed: u64,
+        /// Also close the game
+        #[clap(long)]
+        close: bool,
+    },
+    /// Close a settled game
+    Clean { seed: u64 },
+    /// List games waiting for a second player
+    Games,
+    /// Show a player's stats, yours by default
+    Stats { owner: Option<Pubkey> },
+    /// Deposit into a bot liquidity pool, sol or the token a token pool
+    /// holds. Turned into lp with pool-process-deposit once the pool's games
+    /// have settled
+    PoolDeposit {
+        pool_seed: u64,
+        amount: u64,
+        /// Refunds the deposit instead if it comes to less lp than this
+        #[clap(long, default_value = "1")]
+        min_lp_out: u64,
+    },
+    /// Mint the lp for a deposit made in `epoch`, yours by default
+    PoolProcessDeposit {
+        pool_seed: u64,
+        epoch: u64,
+        #[clap(long)]
+        owner: Option<Pubkey>,
+        /// Defaults to the owner's associated token account for the lp mint
+        #[clap(long)]
+        lp_token_account: Option<Pubkey>,
+    },
+    /// Burn lp tokens for their share of a pool, paid out with
+    /// pool-claim once the pool's current epoch is processed
+    PoolWithdraw {
+        pool_seed: u64,
+        amount: u64,
+        #[clap(long)]
+        lp_token_account: Option<Pubkey>,
+    },
+    /// Redeem a pool's withdrawal requests once its epoch is over and its
+    /// games have settled
+    PoolProcessEpoch { pool_seed: u64 },
+    /// Collect a withdrawal requested in `epoch`
+    PoolClaim { pool_seed: u64, epoch: u64 },
+    /// Copy the vault to a new file under its own passphrase
+    VaultExport { path: PathBuf },
+    /// Add the salts from another vault file
+    VaultImport { path: PathBuf },
+}
+
+#[derive(Clone, Copy, ArgEnum)]
+enum Choice {
+    Rock,
+    Paper,
+    Scissors,
+    Lizard,
+    Spock,
+}
+
+impl From<Choice> for RPS {
+    fn from(choice: Choice) -> Self {
+        match choice {
+            Choice::Rock => RPS::Rock,
+            Choice::Paper => RPS::Paper,
+            Choice::Scissors => RPS::Scissors,
+            Choice::Lizard => RPS::Lizard,
+            Choice::Spock => RPS::Spock,
+        }
+    }
+}
+
+struct Ctx {
+    client: RpcClient,
+    payer: Keypair,
+    vault: Vault,
+}
+
+impl Ctx {
+    fn send(&self, instruction: Instruction) -> Result<()> {
+        let blockhash = self.client.get_latest_blockhash()?;
+        let tx = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&self.payer.pubkey()),
+            &[&self.payer],
+            blockhash,
+        );
+        let signature = self.client.send_and_confirm_transaction(&tx)?;
+        println!("{}", signature);
+        Ok(())
+    }
+
+    fn fetch<T: AccountDeserialize>(&self, key: &Pubkey) -> Result<T> {
+        let data = self
+            .client
+            .get_account_data(key)
+            .with_context(|| format!("fetching {}", key))?;
+        Ok(T::try_deserialize(&mut data.as_slice())?)
+    }
+
+    fn game(&self, seed: u64) -> Result<Game> {
+        self.fetch(&rps_client::rps::game(seed))
+    }
+
+    // the mint a token pool holds along with its token program, none for sol
+    // pools
+    fn pool_mint(&self, pool: &Pool) -> Result<Option<TokenMint>> {
+        let Some(mint) = pool.mint else {
+            return Ok(None);
+        };
+        let account = self
+            .client
+            .get_account(&mint)
+            .with_context(|| format!("fetching {}", mint))?;
+        Ok(Some(TokenMint {
+            mint,
+            token_program: account.owner,
+        }))
+    }
+
+    // a crash or closed terminal after committing shouldn't lose the game
+    fn auto_reveal(&self) {
+        let me = self.payer.pubkey();
+        for entry in self.vault.entries() {
+            let game = match self.game(entry.game_seed) {
+                Ok(game) => game,
+                Err(_) => continue,
+            };
+            if awaiting_reveal(&game, me).is_some() {
+                println!("revealing game {}", entry.game_seed);
+                // one bad game shouldn't stop the rest or the command
+                if let Err(err) = self.send(rps_client::rps::reveal_game(
+                    me,
+                    entry.game_seed,
+                    entry.choice,
+                    entry.salt,
+                )) {
+                    eprintln!("couldn't reveal game {}: {}", entry.game_seed, err);
+                }
+            }
+        }
+    }
+}
+
+// our commitment and the game's config if it's waiting on us to reveal
+fn awaiting_reveal(game: &Game, me: Pubkey) -> Option<([u8; 32], GameConfig)> {
+    match game.state {
+        GameState::AcceptingReveal {
+            player_1,
+            player_2,
+            config,
+            ..
+        } => [player_1, player_2]
+            .into_iter()
+            .find_map(|player| match player {
+                PlayerState::Committed { pubkey, commitment } if pubkey == me => {
+                    Some((commitment, config))
+                }
+                _ => None,
+            }),
+        _ => None,
+    }
+}
+
+fn passphrase(prompt: &str) -> Result<String> {
+    match std::env::var("RPS_VAULT_PASSPHRASE") {
+        Ok(passphrase) => Ok(passphrase),
+        Err(_) => Ok(rpassword::prompt_password(prompt)?),
+    }
+}
+
+fn home() -> Result<PathBuf> {
+    std::env::var_os("HOME")
+        .map(PathBuf::from)
+        .ok_or_else(|| anyhow!("HOME isn't set"))
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let keypair = match cli.keypair {
+        Some(path) => path,
+        None => home()?.join(".config/solana/id.json"),
+    };
+    let vault = match cli.vault {
+        Some(path) => path,
+        None => home()?.join(".config/rps/vault.json"),
+    };
+    let ctx = Ctx {
+        client: RpcClient::new_with_commitment(cli.url, CommitmentConfig::confirmed()),
+        payer: read_keypair_file(&keypair)
+            .map_err(|err| anyhow!("reading {}: {}", keypair.display(), err))?,
+        vault: Vault::open(vault, &passphrase("vault passphrase: ")?)?,
+    };
+    if !cli.no_auto_reveal {
+        ctx.auto_reveal();
+    }
+    run(ctx, cli.command)
+}
+
+fn run(mut ctx: Ctx, command: Command) -> Result<()> {
+    let me = ctx.payer.pubkey();
+    match command {
+        Command::Init => ctx.send(rps_client::rps::create_player_info(me)),
+        Command::Create {
+            wager,
+            choice,
+            seed,
+            secret,
+            challenge_duration,
+            reveal_duration,
+            both_commit,
+            rpsls,
+            v1_commitment,
+        } => {
+            let seed = seed.unwrap_or_else(rand::random);
+            let choice = RPS::from(choice);
+            let commitment_version = if v1_commitment {
+                CommitmentVersion::V1
+            } else {
+                CommitmentVersion::V2
+            };
+            let (salt, commitment) = commitment::commit_derived(
+                &ctx.payer,
+                rps::ID,
+                rps_client::rps::game(seed),
+                seed,
+                0,
+                commitment_version,
+                choice,
+            );
+            let config = GameConfig {
+                entry_proof: secret.map(|secret| commitment::create_entry_proof(me, secret)),
+                challenge_duration,
+                reveal_duration,
+                // salts and the vault only cover the first round, there's no
+                // playing later rounds from here
+                best_of: 1,
+                commit_mode: if both_commit {
+                    CommitMode::BothPlayers
+                } else {
+                    CommitMode::Player1
+                },
+                move_set: if rpsls {
+                    MoveSet::Rpsls
+                } else {
+                    MoveSet::Classic
+                },
+                commitment_version,
+            };
+
+            // saved first, if the transaction lands and we crash the salt
+            // is still there to reveal with
+            ctx.vault.insert(Entry {
+                game_seed: seed,
+                choice,
+                salt,
+            })?;
+            ctx.send(rps_client::rps::create_game(
+                me, seed, commitment, wager, config,
+            ))?;
+            println!("created game {}", seed);
+            Ok(())
+        }
+        Command::Join {
+            seed,
+            choice,
+            secret,
+        } => {
+            let game = ctx.game(seed)?;
+            let config = match game.state {
+                GameState::AcceptingChallenge { config, .. } => config,
+                _ => bail!("game {} isn't waiting for a player", seed),
+            };
+            if config.best_of != 1 {
+                bail!(
+                    "game {} is best of {}, only single throws can be played from here",
+                    seed,
+                    config.best_of
+                );
+            }
+            let choice = RPS::from(choice);
+            match config.commit_mode {
+                CommitMode::Player1 => {
+                    ctx.send(rps_client::rps::join_game(me, seed, choice, secret))
+                }
+                CommitMode::BothPlayers => {
+                    let (salt, commitment) = commitment::commit_derived(
+                        &ctx.payer,
+                        rps::ID,
+                        rps_client::rps::game(seed),
+                        seed,
+                        0,
+                        config.commitment_version,
+                        choice,
+                    );
+                    ctx.vault.insert(Entry {
+                        game_seed: seed,
+                        choice,
+                        salt,
+                    })?;
+                    ctx.send(rps_client::rps::join_game_committed(
+                        me, seed, commitment, secret,
+                    ))
+                }
+            }
+        }
+        Command::Reveal { seed } => {
+            let (choice, salt) = match ctx.vault.get(seed) {
+                Some(entry) => (entry.choice, entry.salt),
+                // lost the vault, the salt can still be derived again if the
+                // move was committed with this keypair
+                None => {
+                    let (commitment, config) = awaiting_reveal(&ctx.game(seed)?, me)
+                        .ok_or_else(|| anyhow!("game {} isn't waiting on us to reveal", seed))?;
+                    commitment::recover_derived(
+                        &ctx.payer,
+                        rps::ID,
+                        rps_client::rps::game(seed),
+                        seed,
+                        0,
+                        config.commitment_version,
+                        commitment,
+                    )
+                    .ok_or_else(|| anyhow!("no salt saved for game {}", seed))?
+                }
+            };
+            ctx.send(rps_client::rps::reveal_game(me, seed, choice, salt))
+        }
+        Command::Expire { seed } => {
+            ctx.send(rps_client::rps::expire_game(me, seed))?;
+            Ok(ctx.vault.remove(seed)?)
+        }
+        Command::Settle { seed, close } => {
+            let game = ctx.game(seed)?;
+            let config: Config = ctx.fetch(&rps_client::rps::config())?;
+            let (player_1, player_2) = match (game.player_1(), game.player_2()) {
+                (Some(player_1), Some(player_2)) => (player_1, player_2),
+                _ => bail!("game {} has no second player", seed),
+            };
+            let instruction = if close {
+                rps_client::rps::settle_and_close(seed, player_1, player_2, config.treasury)
+            } else {
+                rps_client::rps::settle_game(seed, player_1, player_2, config.treasury)
+            };
+            ctx.send(instruction)?;
+            Ok(ctx.vault.remove(seed)?)
+        }
+        Command::Clean { seed } => {
+            let game = ctx.game(seed)?;
+            let player_1 = game
+                .player_1()
+                .ok_or_else(|| anyhow!("game {} has no players", seed))?;
+            ctx.send(rps_client::rps::clean_game(seed, player_1))
+        }
+        Command::Games => {
+            let accounts = ctx.client.get_program_accounts_with_config(
+                &rps::ID,
+                RpcProgramAccountsConfig {
+                    filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                        0,
+                        &Game::discriminator(),
+                    ))]),
+                    account_config: RpcAccountInfoConfig {
+                        encoding: Some(UiAccountEncoding::Base64),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+            )?;
+            for (_, account) in accounts {
+                let game = match Game::try_deserialize(&mut account.data.as_slice()) {
+                    Ok(game) => game,
+                    Err(_) => continue,
+                };
+                if let GameState::AcceptingChallenge {
+                    config,
+                    player_1,
+                    expiry_slot,
+                } = game.state
+                {
+                    println!(
+                        "seed {} wager {} best of {} player 1 {} expires at slot {}{}",
+                        game.seed,
+                        game.wager_amount,
+                        config.best_of,
+                        player_1.pubkey(),
+                        expiry_slot,
+                        if config.entry_proof.is_some() {
+                            " (private)"
+                        } else {
+                            ""
+                        },
+                    );
+                }
+            }
+            Ok(())
+        }
+        Command::Stats { owner } => {
+            let owner = owner.unwrap_or(me);
+            let info: PlayerInfo = ctx.fetch(&rps_client::rps::player_info(owner))?;
+            println!("won {}", info.games_won);
+            println!("drawn {}", info.games_drawn);
+            println!("lost {}", info.games_lost);
+            println!("wagered {}", info.lifetime_wagering);
+            println!("earnings {}", info.lifetime_earnings);
+            println!("in games {}", info.amount_in_games);
+            Ok(())
+        }
+        Command::PoolDeposit {
+            pool_seed,
+            amount,
+            min_lp_out,
+        } => {
+            let pool: Pool = ctx.fetch(&rps_client::blp::pool(pool_seed))?;
+            let instruction = match ctx.pool_mint(&pool)? {
+                Some(mint) => rps_client::blp::deposit_token(
+                    me,
+                    mint.associated_token_account(me),
+                    mint,
+                    pool_seed,
+                    pool.epoch,
+                    amount,
+                    min_lp_out,
+                ),
+                None => rps_client::blp::deposit(me, pool_seed, pool.epoch, amount, min_lp_out),
+            };
+            ctx.send(instruction)?;
+            println!(
+                "queued deposit in epoch {}, processable once the pool's games settle",
+                pool.epoch
+            );
+            Ok(())
+        }
+        Command::PoolProcessDeposit {
+            pool_seed,
+            epoch,
+            owner,
+            lp_token_account,
+        } => {
+            let owner = owner.unwrap_or(me);
+            let lp_token_account =
+                lp_token_account.unwrap_or_else(|| lp_token_account_of(owner, pool_seed));
+            let pool: Pool = ctx.fetch(&rps_client::blp::pool(pool_seed))?;
+            let instruction = match ctx.pool_mint(&pool)? {
+                Some(mint) => rps_client::blp::process_token_deposit(
+                    owner,
+                    mint.associated_token_account(owner),
+                    lp_token_account,
+                    mint,
+                    pool_seed,
+                    epoch,
+                ),
+                None => rps_client::blp::process_deposit(owner, lp_token_account, pool_seed, epoch),
+            };
+            ctx.send(instruction)
+        }
+        Command::PoolWithdraw {
+            pool_seed,
+            amount,
+            lp_token_account,
+        } => {
+            let lp_token_account =
+                lp_token_account.unwrap_or_else(|| lp_token_account_of(me, pool_seed));
+            let pool: Pool = ctx.fetch(&rps_client::blp::pool(pool_seed))?;
+            ctx.send(rps_client::blp::request_withdrawal(
+                me,
+                lp_token_account,
+                pool_seed,
+                pool.epoch,
+                amount,
+            ))?;
+            println!(
+                "requested withdrawal in epoch {}, claimable once it's processed",
+                pool.epoch
+            );
+            Ok(())
+        }
+        Command::PoolProcessEpoch { pool_seed } => {
+            let pool: Pool = ctx.fetch(&rps_client::blp::pool(pool_seed))?;
+            let instruction = match pool.mint {
+                Some(mint) => rps_client::blp::process_token_epoch(me, mint, pool_seed, pool.epoch),
+                None => rps_client::blp::process_epoch(me, pool_seed, pool.epoch),
+            };
+            ctx.send(instruction)
+        }
+        Command::PoolClaim { pool_seed, epoch } => {
+            let pool: Pool = ctx.fetch(&rps_client::blp::pool(pool_seed))?;
+            let instruction = match ctx.pool_mint(&pool)? {
+                Some(mint) => rps_client::blp::claim_token_withdrawal(
+                    me,
+                    mint.associated_token_account(me),
+                    mint,
+                    pool_seed,
+                    epoch,
+                ),
+                None => rps_client::blp::claim_withdrawal(me, pool_seed, epoch),
+            };
+            ctx.send(instruction)
+        }
+        Command::VaultExport { path } => {
+            let passphrase = rpassword::prompt_password("export passphrase: ")?;
+            ctx.vault.export(path, &passphrase)?;
+            Ok(())
+        }
+        Command::VaultImport { path } => {
+            let passphrase = rpassword::prompt_password("import passphrase: ")?;
+            let added = ctx.vault.import(path, &passphrase)?;
+            println!("imported {} salts", added);
+            Ok(())
+        }
+    }
+}
+
+fn lp_token_account_of(owner: Pubkey, pool_seed: u64) -> Pubkey {
+    let lp_token_mint = rps_client::blp::lp_token_mint(rps_client::blp::pool(pool_seed));
+    TokenMint::legacy(lp_token_mint).associated_token_account(owner)
+}
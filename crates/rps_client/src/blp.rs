@@ -0,0 +1,444 @@
+//! Pdas and instruction builders for the bot liquidity pool program.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::system_program;
+use blp::risk::RiskParams;
+use blp::{accounts, instruction as ix, ID};
+use rps_core::logic::RPS;
+
+use crate::rps_token::TokenMint;
+use crate::{find, instruction};
+
+pub fn pool(pool_seed: u64) -> Pubkey {
+    find(&[b"pool".as_ref(), &pool_seed.to_le_bytes()], &ID)
+}
+
+// holds a sol pool's lamports and plays as the pool in rps and rps_token
+pub fn pool_authority(pool: Pubkey) -> Pubkey {
+    find(&[b"authority".as_ref(), pool.as_ref()], &ID)
+}
+
+pub fn lp_token_mint(pool: Pubkey) -> Pubkey {
+    find(&[b"mint".as_ref(), pool.as_ref()], &ID)
+}
+
+pub fn withdrawal_request(pool: Pubkey, owner: Pubkey, epoch: u64) -> Pubkey {
+    find(
+        &[
+            b"withdrawal".as_ref(),
+            pool.as_ref(),
+            owner.as_ref(),
+            &epoch.to_le_bytes(),
+        ],
+        &ID,
+    )
+}
+
+pub fn deposit_request(pool: Pubkey, owner: Pubkey, epoch: u64) -> Pubkey {
+    find(
+        &[
+            b"deposit".as_ref(),
+            pool.as_ref(),
+            owner.as_ref(),
+            &epoch.to_le_bytes(),
+        ],
+        &ID,
+    )
+}
+
+// what withdrawals requested in `epoch` were redeemed at, once processed
+pub fn epoch(pool: Pubkey, epoch: u64) -> Pubkey {
+    find(
+        &[b"epoch".as_ref(), pool.as_ref(), &epoch.to_le_bytes()],
+        &ID,
+    )
+}
+
+pub fn create_pool(
+    bot_authority: Pubkey,
+    pool_seed: u64,
+    epoch_duration: u64,
+    risk: RiskParams,
+    admin: Pubkey,
+) -> Instruction {
+    let pool = pool(pool_seed);
+    let pool_authority = pool_authority(pool);
+    instruction(
+        ID,
+        accounts::CreatePool {
+            pool,
+            pool_authority,
+            pool_authority_player_info: crate::rps::player_info(pool_authority),
+            lp_token_mint: lp_token_mint(pool),
+            bot_authority,
+            token_program: anchor_spl::token::ID,
+            rps_program: rps::ID,
+            system_program: system_program::ID,
+        },
+        ix::CreatePool {
+            seed: pool_seed,
+            epoch_duration,
+            risk,
+            admin,
+        },
+    )
+}
+
+fn pool_admin(admin: Pubkey, pool_seed: u64) -> accounts::PoolAdmin {
+    accounts::PoolAdmin {
+        pool: pool(pool_seed),
+        admin,
+    }
+}
+
+pub fn update_pool(admin: Pubkey, pool_seed: u64, risk: RiskParams) -> Instruction {
+    instruction(ID, pool_admin(admin, pool_seed), ix::UpdatePool { risk })
+}
+
+pub fn set_bot_authority(admin: Pubkey, pool_seed: u64, bot_authority: Pubkey) -> Instruction {
+    instruction(
+        ID,
+        pool_admin(admin, pool_seed),
+        ix::SetBotAuthority { bot_authority },
+    )
+}
+
+pub fn revoke_bot(admin: Pubkey, pool_seed: u64) -> Instruction {
+    instruction(ID, pool_admin(admin, pool_seed), ix::RevokeBot {})
+}
+
+pub fn transfer_admin(admin: Pubkey, pool_seed: u64, new_admin: Pubkey) -> Instruction {
+    instruction(
+        ID,
+        pool_admin(admin, pool_seed),
+        ix::TransferAdmin { new_admin },
+    )
+}
+
+pub fn accept_admin(new_admin: Pubkey, pool_seed: u64) -> Instruction {
+    instruction(
+        ID,
+        accounts::AcceptAdmin {
+            pool: pool(pool_seed),
+            new_admin,
+        },
+        ix::AcceptAdmin {},
+    )
+}
+
+/// Queues a deposit in the pool's current epoch `epoch`, converted to lp
+/// with process_deposit or refunded if that comes to less than `min_lp_out`.
+pub fn deposit(
+    user: Pubkey,
+    pool_seed: u64,
+    epoch: u64,
+    deposit_amount: u64,
+    min_lp_out: u64,
+) -> Instruction {
+    let pool = pool(pool_seed);
+    instruction(
+        ID,
+        accounts::Deposit {
+            pool,
+            pool_authority: pool_authority(pool),
+            deposit_request: deposit_request(pool, user, epoch),
+            user_authority: user,
+            system_program: system_program::ID,
+        },
+        ix::Deposit {
+            deposit_amount,
+            min_lp_out,
+        },
+    )
+}
+
+/// Mints `owner`'s lp for a deposit queued in `epoch`, once the pool's games
+/// have settled.
+pub fn process_deposit(
+    owner: Pubkey,
+    user_lp_token_account: Pubkey,
+    pool_seed: u64,
+    epoch: u64,
+) -> Instruction {
+    let pool = pool(pool_seed);
+    let pool_authority = pool_authority(pool);
+    instruction(
+        ID,
+        accounts::ProcessDeposit {
+            pool,
+            pool_authority,
+            pool_authority_player_info: crate::rps::player_info(pool_authority),
+            lp_token_mint: lp_token_mint(pool),
+            deposit_request: deposit_request(pool, owner, epoch),
+            owner,
+            user_lp_token_account,
+            token_program: anchor_spl::token::ID,
+            system_program: system_program::ID,
+        },
+        ix::ProcessDeposit {},
+    )
+}
+
+/// Burns `lp_amount` now, redeemed once the pool's current epoch `epoch`
+/// is processed.
+pub fn request_withdrawal(
+    user: Pubkey,
+    user_lp_token_account: Pubkey,
+    pool_seed: u64,
+    epoch: u64,
+    lp_amount: u64,
+) -> Instruction {
+    let pool = pool(pool_seed);
+    instruction(
+        ID,
+        accounts::RequestWithdrawal {
+            pool,
+            withdrawal_request: withdrawal_request(pool, user, epoch),
+            lp_token_mint: lp_token_mint(pool),
+            user_authority: user,
+            user_lp_token_account,
+            token_program: anchor_spl::token::ID,
+            system_program: system_program::ID,
+        },
+        ix::RequestWithdrawal { lp_amount },
+    )
+}
+
+pub fn process_epoch(payer: Pubkey, pool_seed: u64, epoch: u64) -> Instruction {
+    let pool = pool(pool_seed);
+    let pool_authority = pool_authority(pool);
+    instruction(
+        ID,
+        accounts::ProcessEpoch {
+            pool,
+            pool_authority,
+            pool_authority_player_info: crate::rps::player_info(pool_authority),
+            lp_token_mint: lp_token_mint(pool),
+            epoch: self::epoch(pool, epoch),
+            payer,
+            system_program: system_program::ID,
+        },
+        ix::ProcessEpoch {},
+    )
+}
+
+/// Pays out a request made in `epoch`.
+pub fn claim_withdrawal(user: Pubkey, pool_seed: u64, epoch: u64) -> Instruction {
+    let pool = pool(pool_seed);
+    instruction(
+        ID,
+        accounts::ClaimWithdrawal {
+            pool,
+            pool_authority: pool_authority(pool),
+            withdrawal_request: withdrawal_request(pool, user, epoch),
+            epoch: self::epoch(pool, epoch),
+            user_authority: user,
+            system_program: system_program::ID,
+        },
+        ix::ClaimWithdrawal {},
+    )
+}
+
+/// Joins the rps game `game_seed` as the pool.
+pub fn bot_play(bot_authority: Pubkey, pool_seed: u64, game_seed: u64, choice: RPS) -> Instruction {
+    let pool = pool(pool_seed);
+    let pool_authority = pool_authority(pool);
+    let game = crate::rps::game(game_seed);
+    instruction(
+        ID,
+        accounts::BotPlay {
+            pool,
+            pool_authority,
+            pool_authority_player_info: crate::rps::player_info(pool_authority),
+            lp_token_mint: lp_token_mint(pool),
+            game,
+            rps_config: crate::rps::config(),
+            game_authority: crate::rps::game_authority(game),
+            bot_authority,
+            rps_program: rps::ID,
+            token_program: anchor_spl::token::ID,
+            system_program: system_program::ID,
+        },
+        ix::BotPlay { choice },
+    )
+}
+
+// token pools hold their tokens here instead of in the pool authority
+pub fn pool_token_account(pool: Pubkey) -> Pubkey {
+    find(&[b"tokens".as_ref(), pool.as_ref()], &ID)
+}
+
+pub fn create_token_pool(
+    bot_authority: Pubkey,
+    mint: TokenMint,
+    pool_seed: u64,
+    epoch_duration: u64,
+    risk: RiskParams,
+    admin: Pubkey,
+) -> Instruction {
+    let pool = pool(pool_seed);
+    let pool_authority = pool_authority(pool);
+    instruction(
+        ID,
+        accounts::CreateTokenPool {
+            pool,
+            pool_authority,
+            pool_authority_player_info: crate::rps_token::player_info(pool_authority, mint.mint),
+            lp_token_mint: lp_token_mint(pool),
+            mint: mint.mint,
+            pool_token_account: pool_token_account(pool),
+            bot_authority,
+            token_program: anchor_spl::token::ID,
+            mint_token_program: mint.token_program,
+            rps_token_program: rps_token::ID,
+            system_program: system_program::ID,
+        },
+        ix::CreateTokenPool {
+            seed: pool_seed,
+            epoch_duration,
+            risk,
+            admin,
+        },
+    )
+}
+
+pub fn deposit_token(
+    user: Pubkey,
+    user_token_account: Pubkey,
+    mint: TokenMint,
+    pool_seed: u64,
+    epoch: u64,
+    deposit_amount: u64,
+    min_lp_out: u64,
+) -> Instruction {
+    let pool = pool(pool_seed);
+    instruction(
+        ID,
+        accounts::DepositToken {
+            pool,
+            pool_authority: pool_authority(pool),
+            pool_token_account: pool_token_account(pool),
+            deposit_request: deposit_request(pool, user, epoch),
+            mint: mint.mint,
+            user_authority: user,
+            user_token_account,
+            mint_token_program: mint.token_program,
+            system_program: system_program::ID,
+        },
+        ix::DepositToken {
+            deposit_amount,
+            min_lp_out,
+        },
+    )
+}
+
+/// Refunds go to `user_token_account` if the deposit comes to less lp than
+/// its minimum.
+pub fn process_token_deposit(
+    owner: Pubkey,
+    user_token_account: Pubkey,
+    user_lp_token_account: Pubkey,
+    mint: TokenMint,
+    pool_seed: u64,
+    epoch: u64,
+) -> Instruction {
+    let pool = pool(pool_seed);
+    let pool_authority = pool_authority(pool);
+    instruction(
+        ID,
+        accounts::ProcessTokenDeposit {
+            pool,
+            pool_authority,
+            pool_token_account: pool_token_account(pool),
+            pool_authority_player_info: crate::rps_token::player_info(pool_authority, mint.mint),
+            lp_token_mint: lp_token_mint(pool),
+            deposit_request: deposit_request(pool, owner, epoch),
+            owner,
+            user_lp_token_account,
+            mint: mint.mint,
+            user_token_account,
+            token_program: anchor_spl::token::ID,
+            mint_token_program: mint.token_program,
+            system_program: system_program::ID,
+        },
+        ix::ProcessTokenDeposit {},
+    )
+}
+
+pub fn process_token_epoch(payer: Pubkey, mint: Pubkey, pool_seed: u64, epoch: u64) -> Instruction {
+    let pool = pool(pool_seed);
+    instruction(
+        ID,
+        accounts::ProcessTokenEpoch {
+            pool,
+            pool_token_account: pool_token_account(pool),
+            pool_authority_player_info: crate::rps_token::player_info(pool_authority(pool), mint),
+            lp_token_mint: lp_token_mint(pool),
+            epoch: self::epoch(pool, epoch),
+            payer,
+            system_program: system_program::ID,
+        },
+        ix::ProcessTokenEpoch {},
+    )
+}
+
+/// Pays out a request made in `epoch` to `user_token_account`.
+pub fn claim_token_withdrawal(
+    user: Pubkey,
+    user_token_account: Pubkey,
+    mint: TokenMint,
+    pool_seed: u64,
+    epoch: u64,
+) -> Instruction {
+    let pool = pool(pool_seed);
+    instruction(
+        ID,
+        accounts::ClaimTokenWithdrawal {
+            pool,
+            pool_authority: pool_authority(pool),
+            pool_token_account: pool_token_account(pool),
+            withdrawal_request: withdrawal_request(pool, user, epoch),
+            epoch: self::epoch(pool, epoch),
+            mint: mint.mint,
+            user_authority: user,
+            user_token_account,
+            mint_token_program: mint.token_program,
+            system_program: system_program::ID,
+        },
+        ix::ClaimTokenWithdrawal {},
+    )
+}
+
+/// Joins the rps_token game `game_seed` as the pool.
+pub fn bot_play_token(
+    bot_authority: Pubkey,
+    mint: TokenMint,
+    pool_seed: u64,
+    game_seed: u64,
+    choice: RPS,
+) -> Instruction {
+    let pool = pool(pool_seed);
+    let pool_authority = pool_authority(pool);
+    let game = crate::rps_token::game(game_seed);
+    instruction(
+        ID,
+        accounts::BotPlayToken {
+            pool,
+            pool_authority,
+            pool_token_account: pool_token_account(pool),
+            pool_authority_player_info: crate::rps_token::player_info(pool_authority, mint.mint),
+            game,
+            rps_config: crate::rps_token::config(),
+            paused_mint: crate::rps_token::paused_mint(mint.mint),
+            game_authority: crate::rps_token::game_authority(game),
+            escrow_token_account: crate::rps_token::escrow(game),
+            mint: mint.mint,
+            bot_authority,
+            rps_token_program: rps_token::ID,
+            mint_token_program: mint.token_program,
+            system_program: system_program::ID,
+        },
+        ix::BotPlayToken { choice },
+    )
+}
@@ -0,0 +1,273 @@
+//! Commitments and entry proofs, hashed the same way `verify_commitment` and
+//! `verify_entry` check them.
+//!
+//! Salts can either be random and kept somewhere safe, or derived from the
+//! player's signature so the keypair alone is enough to reveal.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::keccak::hashv;
+use rps_core::logic::{CommitmentVersion, Salt, RPS};
+use solana_sdk::signer::Signer;
+
+// keeps signatures made for salts from being valid for anything else
+const SALT_DOMAIN: &[u8] = b"rps-salt-v1";
+// has to match `verify_commitment_v2`
+const COMMITMENT_DOMAIN: &[u8] = b"rps-commitment-v2";
+
+pub fn generate_salt() -> u64 {
+    rand::random()
+}
+
+pub fn generate_salt_v2() -> [u8; 32] {
+    rand::random()
+}
+
+pub fn create_commitment(player: Pubkey, salt: u64, choice: RPS) -> [u8; 32] {
+    let choice8: u8 = choice.into();
+    hashv(&[player.as_ref(), &salt.to_le_bytes(), &[choice8]]).0
+}
+
+/// Only valid in the game `game` of the program `program_id`.
+pub fn create_commitment_v2(
+    program_id: Pubkey,
+    game: Pubkey,
+    player: Pubkey,
+    salt: [u8; 32],
+    choice: RPS,
+) -> [u8; 32] {
+    let choice8: u8 = choice.into();
+    hashv(&[
+        COMMITMENT_DOMAIN,
+        program_id.as_ref(),
+        game.as_ref(),
+        player.as_ref(),
+        &salt,
+        &[choice8],
+    ])
+    .0
+}
+
+/// Commitment in whichever version `salt` is for.
+pub fn create_versioned_commitment(
+    program_id: Pubkey,
+    game: Pubkey,
+    player: Pubkey,
+    salt: Salt,
+    choice: RPS,
+) -> [u8; 32] {
+    match salt {
+        Salt::V1(salt) => create_commitment(player, salt, choice),
+        Salt::V2(salt) => create_commitment_v2(program_id, game, player, salt, choice),
+    }
+}
+
+/// Fresh salt and the commitment to `choice`. Keep the salt, it's needed to
+/// reveal.
+pub fn commit(player: Pubkey, choice: RPS) -> (u64, [u8; 32]) {
+    let salt = generate_salt();
+    (salt, create_commitment(player, salt, choice))
+}
+
+pub fn commit_v2(
+    program_id: Pubkey,
+    game: Pubkey,
+    player: Pubkey,
+    choice: RPS,
+) -> ([u8; 32], [u8; 32]) {
+    let salt = generate_salt_v2();
+    (
+        salt,
+        create_commitment_v2(program_id, game, player, salt, choice),
+    )
+}
+
+/// What a player signs to derive the salt for a round of a game. Every round
+/// needs its own salt, otherwise a move revealed in one round would give
+/// away the salt for the next.
+pub fn salt_message(game: Pubkey, game_seed: u64, round: u8) -> Vec<u8> {
+    [
+        SALT_DOMAIN,
+        game.as_ref(),
+        &game_seed.to_le_bytes(),
+        &[round],
+    ]
+    .concat()
+}
+
+// ed25519 signatures are deterministic so signing the same message with the
+// same keypair always gives back the same salt
+fn signed_salt(signer: &dyn Signer, game: Pubkey, game_seed: u64, round: u8) -> [u8; 32] {
+    let signature = signer.sign_message(&salt_message(game, game_seed, round));
+    hashv(&[signature.as_ref()]).0
+}
+
+pub fn derive_salt(signer: &dyn Signer, game: Pubkey, game_seed: u64, round: u8) -> u64 {
+    let salt = signed_salt(signer, game, game_seed, round);
+    u64::from_le_bytes(salt[..8].try_into().unwrap())
+}
+
+pub fn derive_salt_v2(signer: &dyn Signer, game: Pubkey, game_seed: u64, round: u8) -> [u8; 32] {
+    signed_salt(signer, game, game_seed, round)
+}
+
+fn derive_versioned_salt(
+    signer: &dyn Signer,
+    game: Pubkey,
+    game_seed: u64,
+    round: u8,
+    version: CommitmentVersion,
+) -> Salt {
+    match version {
+        CommitmentVersion::V1 => Salt::V1(derive_salt(signer, game, game_seed, round)),
+        CommitmentVersion::V2 => Salt::V2(derive_salt_v2(signer, game, game_seed, round)),
+    }
+}
+
+/// Commits to `choice` with a derived salt, nothing needs to be stored to
+/// reveal later.
+pub fn commit_derived(
+    signer: &dyn Signer,
+    program_id: Pubkey,
+    game: Pubkey,
+    game_seed: u64,
+    round: u8,
+    version: CommitmentVersion,
+    choice: RPS,
+) -> (Salt, [u8; 32]) {
+    let salt = derive_versioned_salt(signer, game, game_seed, round, version);
+    let commitment = create_versioned_commitment(program_id, game, signer.pubkey(), salt, choice);
+    (salt, commitment)
+}
+
+/// Finds the move behind an on-chain commitment made with `commit_derived`.
+pub fn recover_derived(
+    signer: &dyn Signer,
+    program_id: Pubkey,
+    game: Pubkey,
+    game_seed: u64,
+    round: u8,
+    version: CommitmentVersion,
+    commitment: [u8; 32],
+) -> Option<(RPS, Salt)> {
+    let salt = derive_versioned_salt(signer, game, game_seed, round, version);
+    (0..u8::MAX)
+        .map_while(|choice| RPS::try_from(choice).ok())
+        .find(|choice| {
+            create_versioned_commitment(program_id, game, signer.pubkey(), salt, *choice)
+                == commitment
+        })
+        .map(|choice| (choice, salt))
+}
+
+/// Private games are joined by whoever knows the secret, the proof is what
+/// goes in the game's config.
+pub fn create_entry_proof(player_1: Pubkey, secret: u64) -> [u8; 32] {
+    hashv(&[player_1.as_ref(), &secret.to_le_bytes()]).0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rps_core::logic::{verify_commitment, verify_commitment_v2, verify_entry};
+    use solana_sdk::signature::Keypair;
+
+    #[test]
+    fn test_commitment() {
+        let player = Pubkey::new_unique();
+        let (salt, commitment) = commit(player, RPS::Scissors);
+        assert!(verify_commitment(player, commitment, salt, RPS::Scissors));
+        assert!(!verify_commitment(player, commitment, salt, RPS::Rock));
+        assert!(!verify_commitment(
+            Pubkey::new_unique(),
+            commitment,
+            salt,
+            RPS::Scissors
+        ));
+    }
+
+    #[test]
+    fn test_commitment_v2() {
+        let program_id = Pubkey::new_unique();
+        let game = Pubkey::new_unique();
+        let player = Pubkey::new_unique();
+        let (salt, commitment) = commit_v2(program_id, game, player, RPS::Rock);
+        assert!(verify_commitment_v2(
+            program_id,
+            game,
+            player,
+            commitment,
+            salt,
+            RPS::Rock
+        ));
+        assert!(!verify_commitment_v2(
+            program_id,
+            Pubkey::new_unique(),
+            player,
+            commitment,
+            salt,
+            RPS::Rock
+        ));
+        assert!(!verify_commitment_v2(
+            Pubkey::new_unique(),
+            game,
+            player,
+            commitment,
+            salt,
+            RPS::Rock
+        ));
+    }
+
+    #[test]
+    fn test_derived_salt() {
+        let player = Keypair::new();
+        let program_id = Pubkey::new_unique();
+        let game = Pubkey::new_unique();
+
+        for version in [CommitmentVersion::V1, CommitmentVersion::V2] {
+            let (salt, commitment) =
+                commit_derived(&player, program_id, game, 1, 0, version, RPS::Spock);
+            // the keypair alone gets it back
+            assert_eq!(
+                recover_derived(&player, program_id, game, 1, 0, version, commitment),
+                Some((RPS::Spock, salt))
+            );
+            assert_eq!(
+                recover_derived(&player, program_id, game, 1, 1, version, commitment),
+                None
+            );
+        }
+
+        let (salt, commitment) = commit_derived(
+            &player,
+            program_id,
+            game,
+            1,
+            0,
+            CommitmentVersion::V1,
+            RPS::Spock,
+        );
+        let Salt::V1(salt) = salt else {
+            panic!("expected a v1 salt")
+        };
+        assert!(verify_commitment(
+            player.pubkey(),
+            commitment,
+            salt,
+            RPS::Spock
+        ));
+
+        // different for every game, round and player
+        assert_ne!(derive_salt(&player, Pubkey::new_unique(), 1, 0), salt);
+        assert_ne!(derive_salt(&player, game, 2, 0), salt);
+        assert_ne!(derive_salt(&player, game, 1, 1), salt);
+        assert_ne!(derive_salt(&Keypair::new(), game, 1, 0), salt);
+    }
+
+    #[test]
+    fn test_entry_proof() {
+        let player_1 = Pubkey::new_unique();
+        let proof = create_entry_proof(player_1, 42);
+        assert!(verify_entry(player_1, proof, 42));
+        assert!(!verify_entry(player_1, proof, 43));
+    }
+}
@@ -0,0 +1,43 @@
+//! Everything an off-chain client needs to talk to the rps programs without
+//! re-deriving things by hand: pdas, commitments and entry proofs that match
+//! what the programs verify, and an instruction builder per instruction.
+//!
+//! Games are referred to by their seed, every other account the builders
+//! need is derived from it.
+
+pub mod blp;
+pub mod commitment;
+pub mod rps;
+pub mod rps_token;
+pub mod vault;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::InstructionData;
+
+pub use rps_core::logic::{CommitMode, CommitmentVersion, GameConfig, MoveSet, Salt, RPS};
+
+fn instruction(
+    program_id: Pubkey,
+    accounts: impl ToAccountMetas,
+    data: impl InstructionData,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+/// Holds a program's upgrade authority, which is the only key that can
+/// initialize its config.
+pub fn program_data(program_id: Pubkey) -> Pubkey {
+    find(
+        &[program_id.as_ref()],
+        &anchor_lang::solana_program::bpf_loader_upgradeable::ID,
+    )
+}
+
+fn find(seeds: &[&[u8]], program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(seeds, program_id).0
+}
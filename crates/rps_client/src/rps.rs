@@ -0,0 +1,269 @@
+//! Pdas and instruction builders for the sol `rps` program.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::system_program;
+use rps::{accounts, instruction as ix, ConfigParams, ID};
+use rps_core::logic::{GameConfig, Salt, RPS};
+
+use crate::{find, instruction};
+
+pub fn game(game_seed: u64) -> Pubkey {
+    find(&[b"game".as_ref(), &game_seed.to_le_bytes()], &ID)
+}
+
+// holds the game's wagers
+pub fn game_authority(game: Pubkey) -> Pubkey {
+    find(&[b"authority".as_ref(), game.as_ref()], &ID)
+}
+
+pub fn player_info(owner: Pubkey) -> Pubkey {
+    find(&[b"player_info".as_ref(), owner.as_ref()], &ID)
+}
+
+pub fn config() -> Pubkey {
+    find(&[b"config".as_ref()], &ID)
+}
+
+pub fn create_player_info(owner: Pubkey) -> Instruction {
+    instruction(
+        ID,
+        accounts::CreatePlayerInfo {
+            owner,
+            player_info: player_info(owner),
+            system_program: system_program::ID,
+        },
+        ix::CreatePlayerInfo {},
+    )
+}
+
+/// `payer` has to be the program's upgrade authority.
+pub fn initialize_config(payer: Pubkey, params: ConfigParams) -> Instruction {
+    instruction(
+        ID,
+        accounts::InitializeConfig {
+            config: config(),
+            payer,
+            program: ID,
+            program_data: crate::program_data(ID),
+            system_program: system_program::ID,
+        },
+        ix::InitializeConfig { params },
+    )
+}
+
+pub fn update_config(admin: Pubkey, params: ConfigParams) -> Instruction {
+    instruction(
+        ID,
+        accounts::UpdateConfig {
+            config: config(),
+            admin,
+        },
+        ix::UpdateConfig { params },
+    )
+}
+
+pub fn set_paused(authority: Pubkey, paused: bool) -> Instruction {
+    instruction(
+        ID,
+        accounts::SetPaused {
+            config: config(),
+            authority,
+        },
+        ix::SetPaused { paused },
+    )
+}
+
+pub fn create_game(
+    player: Pubkey,
+    game_seed: u64,
+    commitment: [u8; 32],
+    wager_amount: u64,
+    game_config: GameConfig,
+) -> Instruction {
+    let game = game(game_seed);
+    instruction(
+        ID,
+        accounts::CreateGame {
+            game,
+            config: config(),
+            player,
+            player_info: player_info(player),
+            game_authority: game_authority(game),
+            system_program: system_program::ID,
+        },
+        ix::CreateGame {
+            game_seed,
+            commitment,
+            wager_amount,
+            config: game_config,
+        },
+    )
+}
+
+fn join_game_accounts(player: Pubkey, game_seed: u64) -> accounts::JoinGame {
+    let game = game(game_seed);
+    accounts::JoinGame {
+        player,
+        player_info: player_info(player),
+        game,
+        config: config(),
+        game_authority: game_authority(game),
+        system_program: system_program::ID,
+    }
+}
+
+pub fn join_game(player: Pubkey, game_seed: u64, choice: RPS, secret: Option<u64>) -> Instruction {
+    instruction(
+        ID,
+        join_game_accounts(player, game_seed),
+        ix::JoinGame { choice, secret },
+    )
+}
+
+pub fn join_game_committed(
+    player: Pubkey,
+    game_seed: u64,
+    commitment: [u8; 32],
+    secret: Option<u64>,
+) -> Instruction {
+    instruction(
+        ID,
+        join_game_accounts(player, game_seed),
+        ix::JoinGameCommitted { commitment, secret },
+    )
+}
+
+pub fn reveal_game(player: Pubkey, game_seed: u64, choice: RPS, salt: Salt) -> Instruction {
+    instruction(
+        ID,
+        accounts::RevealGame {
+            game: game(game_seed),
+            player,
+            player_info: player_info(player),
+        },
+        ix::RevealGame { choice, salt },
+    )
+}
+
+pub fn commit_round(player: Pubkey, game_seed: u64, commitment: [u8; 32]) -> Instruction {
+    instruction(
+        ID,
+        accounts::CommitRound {
+            game: game(game_seed),
+            player,
+        },
+        ix::CommitRound { commitment },
+    )
+}
+
+pub fn play_round(player: Pubkey, game_seed: u64, choice: RPS) -> Instruction {
+    instruction(
+        ID,
+        accounts::PlayRound {
+            game: game(game_seed),
+            player,
+        },
+        ix::PlayRound { choice },
+    )
+}
+
+pub fn play_round_committed(player: Pubkey, game_seed: u64, commitment: [u8; 32]) -> Instruction {
+    instruction(
+        ID,
+        accounts::PlayRound {
+            game: game(game_seed),
+            player,
+        },
+        ix::PlayRoundCommitted { commitment },
+    )
+}
+
+// doesn't need a signature, `player` is whoever the game defaults to
+pub fn expire_game(player: Pubkey, game_seed: u64) -> Instruction {
+    instruction(
+        ID,
+        accounts::ExpireGame {
+            game: game(game_seed),
+            player,
+            player_info: player_info(player),
+        },
+        ix::ExpireGame {},
+    )
+}
+
+pub fn cancel_game(player: Pubkey, game_seed: u64) -> Instruction {
+    let game = game(game_seed);
+    instruction(
+        ID,
+        accounts::CancelGame {
+            game,
+            player,
+            player_info: player_info(player),
+            game_authority: game_authority(game),
+            system_program: system_program::ID,
+        },
+        ix::CancelGame {},
+    )
+}
+
+fn settle_game_accounts(
+    game_seed: u64,
+    player_1: Pubkey,
+    player_2: Pubkey,
+    treasury: Pubkey,
+) -> accounts::SettleGame {
+    let game = game(game_seed);
+    accounts::SettleGame {
+        game,
+        player_1,
+        player_1_info: player_info(player_1),
+        player_2,
+        player_2_info: player_info(player_2),
+        game_authority: game_authority(game),
+        config: config(),
+        treasury,
+        system_program: system_program::ID,
+    }
+}
+
+pub fn settle_game(
+    game_seed: u64,
+    player_1: Pubkey,
+    player_2: Pubkey,
+    treasury: Pubkey,
+) -> Instruction {
+    instruction(
+        ID,
+        settle_game_accounts(game_seed, player_1, player_2, treasury),
+        ix::SettleGame {},
+    )
+}
+
+pub fn settle_and_close(
+    game_seed: u64,
+    player_1: Pubkey,
+    player_2: Pubkey,
+    treasury: Pubkey,
+) -> Instruction {
+    instruction(
+        ID,
+        settle_game_accounts(game_seed, player_1, player_2, treasury),
+        ix::SettleAndClose {},
+    )
+}
+
+pub fn clean_game(game_seed: u64, player_1: Pubkey) -> Instruction {
+    let game = game(game_seed);
+    instruction(
+        ID,
+        accounts::CleanGame {
+            game,
+            game_authority: game_authority(game),
+            player_1,
+            system_program: system_program::ID,
+            rps_program: ID,
+        },
+        ix::CleanGame {},
+    )
+}
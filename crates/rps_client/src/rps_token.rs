@@ -0,0 +1,446 @@
+//! Pdas and instruction builders for the `rps_token` program. Token accounts
+//! are always passed in explicitly since they can be any account of the mint,
+//! or the player's wallet for native mint games.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::system_program;
+use rps_core::logic::{GameConfig, Salt, RPS};
+use rps_token::{accounts, instruction as ix, ConfigParams, MintConfigParams, ID};
+
+use crate::{find, instruction};
+
+/// A mint along with the token program that owns it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenMint {
+    pub mint: Pubkey,
+    pub token_program: Pubkey,
+}
+
+impl TokenMint {
+    pub fn legacy(mint: Pubkey) -> Self {
+        TokenMint {
+            mint,
+            token_program: anchor_spl::token::ID,
+        }
+    }
+    pub fn token_2022(mint: Pubkey) -> Self {
+        TokenMint {
+            mint,
+            token_program: spl_token_2022::ID,
+        }
+    }
+    pub fn associated_token_account(&self, owner: Pubkey) -> Pubkey {
+        spl_associated_token_account::get_associated_token_address_with_program_id(
+            &owner,
+            &self.mint,
+            &self.token_program,
+        )
+    }
+}
+
+pub fn game(game_seed: u64) -> Pubkey {
+    find(&[b"game".as_ref(), &game_seed.to_le_bytes()], &ID)
+}
+
+// owns the escrow token account
+pub fn game_authority(game: Pubkey) -> Pubkey {
+    find(&[b"authority".as_ref(), game.as_ref()], &ID)
+}
+
+pub fn escrow(game: Pubkey) -> Pubkey {
+    find(&[b"escrow".as_ref(), game.as_ref()], &ID)
+}
+
+pub fn player_info(owner: Pubkey, mint: Pubkey) -> Pubkey {
+    find(
+        &[b"player_info".as_ref(), owner.as_ref(), mint.as_ref()],
+        &ID,
+    )
+}
+
+pub fn config() -> Pubkey {
+    find(&[b"config".as_ref()], &ID)
+}
+
+pub fn paused_mint(mint: Pubkey) -> Pubkey {
+    find(&[b"paused_mint".as_ref(), mint.as_ref()], &ID)
+}
+
+pub fn mint_config(mint: Pubkey) -> Pubkey {
+    find(&[b"mint_config".as_ref(), mint.as_ref()], &ID)
+}
+
+pub fn create_player_info(owner: Pubkey, mint: Pubkey) -> Instruction {
+    instruction(
+        ID,
+        accounts::CreatePlayerInfo {
+            owner,
+            mint,
+            player_info: player_info(owner, mint),
+            system_program: system_program::ID,
+        },
+        ix::CreatePlayerInfo {},
+    )
+}
+
+/// `payer` has to be the program's upgrade authority.
+pub fn initialize_config(payer: Pubkey, params: ConfigParams) -> Instruction {
+    instruction(
+        ID,
+        accounts::InitializeConfig {
+            config: config(),
+            payer,
+            program: ID,
+            program_data: crate::program_data(ID),
+            system_program: system_program::ID,
+        },
+        ix::InitializeConfig { params },
+    )
+}
+
+pub fn update_config(admin: Pubkey, params: ConfigParams) -> Instruction {
+    instruction(
+        ID,
+        accounts::UpdateConfig {
+            config: config(),
+            admin,
+        },
+        ix::UpdateConfig { params },
+    )
+}
+
+pub fn set_paused(authority: Pubkey, paused: bool) -> Instruction {
+    instruction(
+        ID,
+        accounts::SetPaused {
+            config: config(),
+            authority,
+        },
+        ix::SetPaused { paused },
+    )
+}
+
+pub fn pause_mint(authority: Pubkey, mint: Pubkey) -> Instruction {
+    instruction(
+        ID,
+        accounts::PauseMint {
+            config: config(),
+            authority,
+            mint,
+            paused_mint: paused_mint(mint),
+            system_program: system_program::ID,
+        },
+        ix::PauseMint {},
+    )
+}
+
+pub fn unpause_mint(authority: Pubkey, mint: Pubkey) -> Instruction {
+    instruction(
+        ID,
+        accounts::UnpauseMint {
+            config: config(),
+            authority,
+            paused_mint: paused_mint(mint),
+        },
+        ix::UnpauseMint {},
+    )
+}
+
+/// `allow_freeze_authority` has to be set to add a mint that can freeze
+/// accounts.
+pub fn add_mint(
+    admin: Pubkey,
+    mint: Pubkey,
+    params: MintConfigParams,
+    allow_freeze_authority: bool,
+) -> Instruction {
+    instruction(
+        ID,
+        accounts::AddMint {
+            config: config(),
+            admin,
+            mint,
+            mint_config: mint_config(mint),
+            system_program: system_program::ID,
+        },
+        ix::AddMint {
+            params,
+            allow_freeze_authority,
+        },
+    )
+}
+
+pub fn update_mint(admin: Pubkey, mint: Pubkey, params: MintConfigParams) -> Instruction {
+    instruction(
+        ID,
+        accounts::UpdateMint {
+            config: config(),
+            admin,
+            mint_config: mint_config(mint),
+        },
+        ix::UpdateMint { params },
+    )
+}
+
+pub fn disable_mint(admin: Pubkey, mint: Pubkey) -> Instruction {
+    instruction(
+        ID,
+        accounts::UpdateMint {
+            config: config(),
+            admin,
+            mint_config: mint_config(mint),
+        },
+        ix::DisableMint {},
+    )
+}
+
+/// What a new game wagers, `commitment` is player 1's first move.
+#[derive(Debug, Clone, Copy)]
+pub struct NewGame {
+    pub game_seed: u64,
+    pub commitment: [u8; 32],
+    pub wager_amount: u64,
+    pub config: GameConfig,
+}
+
+pub fn create_game(
+    player: Pubkey,
+    player_token_account: Pubkey,
+    mint: TokenMint,
+    new_game: NewGame,
+) -> Instruction {
+    let game = game(new_game.game_seed);
+    instruction(
+        ID,
+        accounts::CreateGame {
+            game,
+            config: config(),
+            player,
+            mint: mint.mint,
+            mint_config: mint_config(mint.mint),
+            paused_mint: paused_mint(mint.mint),
+            player_token_account,
+            player_info: player_info(player, mint.mint),
+            game_authority: game_authority(game),
+            escrow_token_account: escrow(game),
+            token_program: mint.token_program,
+            associated_token_program: anchor_spl::associated_token::ID,
+            system_program: system_program::ID,
+        },
+        ix::CreateGame {
+            game_seed: new_game.game_seed,
+            commitment: new_game.commitment,
+            wager_amount: new_game.wager_amount,
+            config: new_game.config,
+        },
+    )
+}
+
+fn join_game_accounts(
+    player: Pubkey,
+    player_token_account: Pubkey,
+    mint: TokenMint,
+    game_seed: u64,
+) -> accounts::JoinGame {
+    let game = game(game_seed);
+    accounts::JoinGame {
+        player,
+        player_token_account,
+        player_info: player_info(player, mint.mint),
+        game,
+        config: config(),
+        paused_mint: paused_mint(mint.mint),
+        game_authority: game_authority(game),
+        escrow_token_account: escrow(game),
+        mint: mint.mint,
+        token_program: mint.token_program,
+        system_program: system_program::ID,
+    }
+}
+
+pub fn join_game(
+    player: Pubkey,
+    player_token_account: Pubkey,
+    mint: TokenMint,
+    game_seed: u64,
+    choice: RPS,
+    secret: Option<u64>,
+) -> Instruction {
+    instruction(
+        ID,
+        join_game_accounts(player, player_token_account, mint, game_seed),
+        ix::JoinGame { choice, secret },
+    )
+}
+
+pub fn join_game_committed(
+    player: Pubkey,
+    player_token_account: Pubkey,
+    mint: TokenMint,
+    game_seed: u64,
+    commitment: [u8; 32],
+    secret: Option<u64>,
+) -> Instruction {
+    instruction(
+        ID,
+        join_game_accounts(player, player_token_account, mint, game_seed),
+        ix::JoinGameCommitted { commitment, secret },
+    )
+}
+
+pub fn reveal_game(
+    player: Pubkey,
+    mint: Pubkey,
+    game_seed: u64,
+    choice: RPS,
+    salt: Salt,
+) -> Instruction {
+    instruction(
+        ID,
+        accounts::RevealGame {
+            game: game(game_seed),
+            player,
+            player_info: player_info(player, mint),
+        },
+        ix::RevealGame { choice, salt },
+    )
+}
+
+pub fn commit_round(player: Pubkey, game_seed: u64, commitment: [u8; 32]) -> Instruction {
+    instruction(
+        ID,
+        accounts::CommitRound {
+            game: game(game_seed),
+            player,
+        },
+        ix::CommitRound { commitment },
+    )
+}
+
+pub fn play_round(player: Pubkey, game_seed: u64, choice: RPS) -> Instruction {
+    instruction(
+        ID,
+        accounts::PlayRound {
+            game: game(game_seed),
+            player,
+        },
+        ix::PlayRound { choice },
+    )
+}
+
+pub fn play_round_committed(player: Pubkey, game_seed: u64, commitment: [u8; 32]) -> Instruction {
+    instruction(
+        ID,
+        accounts::PlayRound {
+            game: game(game_seed),
+            player,
+        },
+        ix::PlayRoundCommitted { commitment },
+    )
+}
+
+// doesn't need a signature, `player` is whoever the game defaults to
+pub fn expire_game(player: Pubkey, mint: Pubkey, game_seed: u64) -> Instruction {
+    instruction(
+        ID,
+        accounts::ExpireGame {
+            game: game(game_seed),
+            player,
+            player_info: player_info(player, mint),
+        },
+        ix::ExpireGame {},
+    )
+}
+
+pub fn cancel_game(
+    player: Pubkey,
+    player_token_account: Pubkey,
+    mint: TokenMint,
+    game_seed: u64,
+) -> Instruction {
+    let game = game(game_seed);
+    instruction(
+        ID,
+        accounts::CancelGame {
+            game,
+            player,
+            player_token_account,
+            player_info: player_info(player, mint.mint),
+            game_authority: game_authority(game),
+            escrow_token_account: escrow(game),
+            mint: mint.mint,
+            token_program: mint.token_program,
+            system_program: system_program::ID,
+        },
+        ix::CancelGame {},
+    )
+}
+
+/// Who a settled game pays out to.
+#[derive(Debug, Clone, Copy)]
+pub struct Settlement {
+    pub player_1: Pubkey,
+    pub player_1_token_account: Pubkey,
+    pub player_2: Pubkey,
+    pub player_2_token_account: Pubkey,
+    pub treasury_token_account: Pubkey,
+}
+
+fn settle_game_accounts(
+    mint: TokenMint,
+    game_seed: u64,
+    settlement: Settlement,
+) -> accounts::SettleGame {
+    let game = game(game_seed);
+    accounts::SettleGame {
+        game,
+        player_1: settlement.player_1,
+        player1_token_account: settlement.player_1_token_account,
+        player_1_info: player_info(settlement.player_1, mint.mint),
+        player_2: settlement.player_2,
+        player2_token_account: settlement.player_2_token_account,
+        player_2_info: player_info(settlement.player_2, mint.mint),
+        game_authority: game_authority(game),
+        escrow_token_account: escrow(game),
+        mint: mint.mint,
+        config: config(),
+        treasury_token_account: settlement.treasury_token_account,
+        token_program: mint.token_program,
+        system_program: system_program::ID,
+    }
+}
+
+pub fn settle_game(mint: TokenMint, game_seed: u64, settlement: Settlement) -> Instruction {
+    instruction(
+        ID,
+        settle_game_accounts(mint, game_seed, settlement),
+        ix::SettleGame {},
+    )
+}
+
+pub fn settle_and_close(mint: TokenMint, game_seed: u64, settlement: Settlement) -> Instruction {
+    instruction(
+        ID,
+        settle_game_accounts(mint, game_seed, settlement),
+        ix::SettleAndClose {},
+    )
+}
+
+pub fn clean_game(mint: TokenMint, game_seed: u64, player_1: Pubkey) -> Instruction {
+    let game = game(game_seed);
+    instruction(
+        ID,
+        accounts::CleanGame {
+            game,
+            game_authority: game_authority(game),
+            escrow_token_account: escrow(game),
+            mint: mint.mint,
+            player_1,
+            token_program: mint.token_program,
+            system_program: system_program::ID,
+            rps_program: ID,
+        },
+        ix::CleanGame {},
+    )
+}
@@ -0,0 +1,308 @@
+//! An encrypted file of every move we've committed to. Losing a salt means
+//! never being able to reveal and losing the game on expiry, so entries are
+//! written to disk before the transaction that commits to them is sent and
+//! only removed once the game is over.
+//!
+//! The file is json with the entries encrypted by AES-256-GCM-SIV under a key
+//! derived from the passphrase with PBKDF2-HMAC-SHA256.
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use aes_gcm_siv::aead::{Aead, NewAead};
+use aes_gcm_siv::{Aes256GcmSiv, Key, Nonce};
+use rps_core::logic::{Salt, RPS};
+use serde::{Deserialize, Serialize};
+
+const VERSION: u8 = 1;
+
+#[cfg(not(test))]
+const KDF_ROUNDS: u32 = 600_000;
+// the real thing takes seconds in debug builds
+#[cfg(test)]
+const KDF_ROUNDS: u32 = 1_000;
+
+#[derive(Debug, thiserror::Error)]
+pub enum VaultError {
+    #[error("wrong passphrase or corrupted vault")]
+    WrongPassphrase,
+    #[error("unsupported vault version {0}")]
+    UnsupportedVersion(u8),
+    #[error("malformed vault: {0}")]
+    Malformed(#[from] serde_json::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// A move we committed to, everything `reveal_game` needs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    pub game_seed: u64,
+    pub choice: RPS,
+    pub salt: Salt,
+}
+
+#[derive(Serialize, Deserialize)]
+struct VaultFile {
+    version: u8,
+    kdf_rounds: u32,
+    #[serde(with = "base64_bytes")]
+    kdf_salt: Vec<u8>,
+    #[serde(with = "base64_bytes")]
+    nonce: Vec<u8>,
+    #[serde(with = "base64_bytes")]
+    ciphertext: Vec<u8>,
+}
+
+pub struct Vault {
+    path: PathBuf,
+    kdf_rounds: u32,
+    kdf_salt: [u8; 16],
+    key: [u8; 32],
+    entries: BTreeMap<u64, Entry>,
+}
+
+impl Vault {
+    /// Opens the vault at `path`, creating an empty one if there's nothing
+    /// there yet.
+    pub fn open(path: impl Into<PathBuf>, passphrase: &str) -> Result<Self, VaultError> {
+        let path = path.into();
+        if !path.exists() {
+            let kdf_salt: [u8; 16] = rand::random();
+            let vault = Vault {
+                key: derive_key(passphrase, &kdf_salt, KDF_ROUNDS),
+                path,
+                kdf_rounds: KDF_ROUNDS,
+                kdf_salt,
+                entries: BTreeMap::new(),
+            };
+            vault.save()?;
+            return Ok(vault);
+        }
+
+        read(path, passphrase)
+    }
+
+    pub fn get(&self, game_seed: u64) -> Option<Entry> {
+        self.entries.get(&game_seed).copied()
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &Entry> {
+        self.entries.values()
+    }
+
+    pub fn insert(&mut self, entry: Entry) -> Result<(), VaultError> {
+        self.entries.insert(entry.game_seed, entry);
+        self.save()
+    }
+
+    pub fn remove(&mut self, game_seed: u64) -> Result<(), VaultError> {
+        if self.entries.remove(&game_seed).is_some() {
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    /// Writes a copy of every entry to a new vault under its own passphrase.
+    pub fn export(&self, path: impl AsRef<Path>, passphrase: &str) -> Result<(), VaultError> {
+        let kdf_salt: [u8; 16] = rand::random();
+        let key = derive_key(passphrase, &kdf_salt, KDF_ROUNDS);
+        write(path.as_ref(), KDF_ROUNDS, &kdf_salt, &key, &self.entries)
+    }
+
+    /// Adds every entry from another vault, entries we already have win.
+    /// Returns how many were added.
+    pub fn import(
+        &mut self,
+        path: impl AsRef<Path>,
+        passphrase: &str,
+    ) -> Result<usize, VaultError> {
+        let entries = read(path.as_ref().to_path_buf(), passphrase)?.entries;
+        let before = self.entries.len();
+        for (game_seed, entry) in entries {
+            self.entries.entry(game_seed).or_insert(entry);
+        }
+        let added = self.entries.len() - before;
+        if added > 0 {
+            self.save()?;
+        }
+        Ok(added)
+    }
+
+    fn save(&self) -> Result<(), VaultError> {
+        write(
+            &self.path,
+            self.kdf_rounds,
+            &self.kdf_salt,
+            &self.key,
+            &self.entries,
+        )
+    }
+}
+
+fn derive_key(passphrase: &str, kdf_salt: &[u8], rounds: u32) -> [u8; 32] {
+    let mut key = [0; 32];
+    pbkdf2::pbkdf2::<hmac::Hmac<sha2::Sha256>>(passphrase.as_bytes(), kdf_salt, rounds, &mut key);
+    key
+}
+
+fn read(path: PathBuf, passphrase: &str) -> Result<Vault, VaultError> {
+    let file: VaultFile = serde_json::from_slice(&fs::read(&path)?)?;
+    if file.version != VERSION {
+        return Err(VaultError::UnsupportedVersion(file.version));
+    }
+    let kdf_salt: [u8; 16] = file
+        .kdf_salt
+        .try_into()
+        .map_err(|_| VaultError::WrongPassphrase)?;
+    if file.nonce.len() != 12 {
+        return Err(VaultError::WrongPassphrase);
+    }
+    let key = derive_key(passphrase, &kdf_salt, file.kdf_rounds);
+    let plaintext = Aes256GcmSiv::new(Key::from_slice(&key))
+        .decrypt(Nonce::from_slice(&file.nonce), file.ciphertext.as_ref())
+        .map_err(|_| VaultError::WrongPassphrase)?;
+    Ok(Vault {
+        path,
+        kdf_rounds: file.kdf_rounds,
+        kdf_salt,
+        key,
+        entries: serde_json::from_slice(&plaintext)?,
+    })
+}
+
+// written to a temp file, synced and renamed over the old one so a crash
+// midway leaves either the old or the new vault, never half of one
+fn write(
+    path: &Path,
+    kdf_rounds: u32,
+    kdf_salt: &[u8],
+    key: &[u8; 32],
+    entries: &BTreeMap<u64, Entry>,
+) -> Result<(), VaultError> {
+    let nonce: [u8; 12] = rand::random();
+    let ciphertext = Aes256GcmSiv::new(Key::from_slice(key))
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            serde_json::to_vec(entries)?.as_ref(),
+        )
+        .expect("encrypting in memory can't fail");
+    let file = VaultFile {
+        version: VERSION,
+        kdf_rounds,
+        kdf_salt: kdf_salt.to_vec(),
+        nonce: nonce.to_vec(),
+        ciphertext,
+    };
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let tmp = path.with_extension("tmp");
+    let mut out = File::create(&tmp)?;
+    out.write_all(&serde_json::to_vec_pretty(&file)?)?;
+    out.sync_all()?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+mod base64_bytes {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&base64::encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        base64::decode(String::deserialize(deserializer)?).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rps-vault-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn entry(game_seed: u64) -> Entry {
+        Entry {
+            game_seed,
+            choice: RPS::Paper,
+            salt: Salt::V1(42 + game_seed),
+        }
+    }
+
+    #[test]
+    fn test_vault() {
+        let dir = temp_dir("open");
+        let path = dir.join("vault.json");
+
+        let mut vault = Vault::open(&path, "hunter2").unwrap();
+        assert_eq!(vault.get(7), None);
+        vault.insert(entry(7)).unwrap();
+
+        // survives reopening, only with the right passphrase
+        let mut vault = Vault::open(&path, "hunter2").unwrap();
+        assert_eq!(vault.get(7), Some(entry(7)));
+        assert!(matches!(
+            Vault::open(&path, "hunter3"),
+            Err(VaultError::WrongPassphrase)
+        ));
+
+        // nothing about the entries is in the clear
+        assert!(!fs::read_to_string(&path).unwrap().contains("Paper"));
+
+        vault.remove(7).unwrap();
+        assert_eq!(Vault::open(&path, "hunter2").unwrap().get(7), None);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_entry_salts() {
+        // entries from before v2 salts still read as v1
+        let entry: Entry =
+            serde_json::from_str(r#"{"game_seed":1,"choice":"Paper","salt":43}"#).unwrap();
+        assert_eq!(entry.salt, Salt::V1(43));
+
+        let v2 = Entry {
+            salt: Salt::V2([3; 32]),
+            ..entry
+        };
+        let json = serde_json::to_string(&v2).unwrap();
+        assert_eq!(serde_json::from_str::<Entry>(&json).unwrap(), v2);
+    }
+
+    #[test]
+    fn test_export_import() {
+        let dir = temp_dir("export");
+        let mut vault = Vault::open(dir.join("a.json"), "a").unwrap();
+        vault.insert(entry(1)).unwrap();
+        vault.insert(entry(2)).unwrap();
+        vault.export(dir.join("backup.json"), "backup").unwrap();
+
+        let mut other = Vault::open(dir.join("b.json"), "b").unwrap();
+        other
+            .insert(Entry {
+                salt: Salt::V1(0),
+                ..entry(2)
+            })
+            .unwrap();
+        assert!(matches!(
+            other.import(dir.join("backup.json"), "a"),
+            Err(VaultError::WrongPassphrase)
+        ));
+        assert_eq!(other.import(dir.join("backup.json"), "backup").unwrap(), 1);
+        assert_eq!(other.get(1), Some(entry(1)));
+        // doesn't overwrite what we already had
+        assert_eq!(other.get(2).unwrap().salt, Salt::V1(0));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
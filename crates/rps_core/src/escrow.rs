@@ -0,0 +1,281 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::{invoke, invoke_signed};
+
+use crate::token_interface;
+
+/// Where a game's wagers and fees sit until it settles. Anything leaving the
+/// escrow is signed for by the game authority pda.
+pub trait Escrow<'info> {
+    /// `from` is the player's wallet for sol and their token account for spl,
+    /// `owner` signs for it. Returns how much actually landed in escrow.
+    fn deposit(
+        &self,
+        from: AccountInfo<'info>,
+        owner: AccountInfo<'info>,
+        amount: u64,
+    ) -> Result<u64>;
+
+    fn withdraw(
+        &self,
+        to: AccountInfo<'info>,
+        amount: u64,
+        signer_seeds: &[&[&[u8]]],
+    ) -> Result<()>;
+
+    /// Hands back whatever rent the escrow holds once the game is done.
+    fn close(&self, destination: AccountInfo<'info>, signer_seeds: &[&[&[u8]]]) -> Result<()>;
+}
+
+/// Native sol held directly by the game authority.
+pub struct SolEscrow<'info> {
+    pub game_authority: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+}
+
+impl<'info> Escrow<'info> for SolEscrow<'info> {
+    fn deposit(
+        &self,
+        from: AccountInfo<'info>,
+        _owner: AccountInfo<'info>,
+        amount: u64,
+    ) -> Result<u64> {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                self.system_program.clone(),
+                anchor_lang::system_program::Transfer {
+                    from,
+                    to: self.game_authority.clone(),
+                },
+            ),
+            amount,
+        )?;
+        Ok(amount)
+    }
+
+    fn withdraw(
+        &self,
+        to: AccountInfo<'info>,
+        amount: u64,
+        signer_seeds: &[&[&[u8]]],
+    ) -> Result<()> {
+        anchor_lang::system_program::transfer(
+            CpiContext::new_with_signer(
+                self.system_program.clone(),
+                anchor_lang::system_program::Transfer {
+                    from: self.game_authority.clone(),
+                    to,
+                },
+                signer_seeds,
+            ),
+            amount,
+        )
+    }
+
+    // the game authority is a plain system account, nothing to close
+    fn close(&self, _destination: AccountInfo<'info>, _signer_seeds: &[&[&[u8]]]) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Tokens from either token program held in a token account owned by the
+/// game authority. Transfers go through `transfer_checked` since Token-2022
+/// requires it for transfer fee mints. Native mint escrows also take and pay
+/// out plain sol, see `deposit` and `pay_out`.
+pub struct TokenEscrow<'info> {
+    pub escrow_token_account: AccountInfo<'info>,
+    pub mint: AccountInfo<'info>,
+    pub game_authority: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+}
+
+impl<'info> TokenEscrow<'info> {
+    fn mint_info(&self) -> Result<token_interface::MintInfo> {
+        token_interface::mint_info(&self.mint).map_err(|_| ProgramError::InvalidAccountData.into())
+    }
+
+    fn balance(&self) -> Result<u64> {
+        token_interface::token_account(&self.escrow_token_account)
+            .map(|account| account.amount)
+            .ok_or_else(|| ProgramError::InvalidAccountData.into())
+    }
+
+    /// Withdraws every payout. Wallets can only be paid from a native mint
+    /// escrow: wrapped lamports only come out by closing the escrow, so they
+    /// go through the game authority and the escrow's rent left over goes to
+    /// `rent_destination`.
+    pub fn pay_out(
+        &self,
+        payouts: &[(AccountInfo<'info>, u64)],
+        rent_destination: AccountInfo<'info>,
+        signer_seeds: &[&[&[u8]]],
+    ) -> Result<()> {
+        let mut unwrapped = vec![];
+        for (to, amount) in payouts {
+            if token_interface::is_wallet(to) {
+                unwrapped.push((to.clone(), *amount));
+            } else {
+                self.withdraw(to.clone(), *amount, signer_seeds)?;
+            }
+        }
+        if unwrapped.is_empty() {
+            return Ok(());
+        }
+        if !token_interface::is_native_mint(self.mint.key) {
+            return Err(ProgramError::InvalidArgument.into());
+        }
+
+        self.close(self.game_authority.clone(), signer_seeds)?;
+        let sol = SolEscrow {
+            game_authority: self.game_authority.clone(),
+            system_program: self.system_program.clone(),
+        };
+        for (to, amount) in unwrapped {
+            if amount > 0 {
+                sol.withdraw(to, amount, signer_seeds)?;
+            }
+        }
+        sol.withdraw(
+            rent_destination,
+            self.game_authority.lamports(),
+            signer_seeds,
+        )
+    }
+}
+
+impl<'info> Escrow<'info> for TokenEscrow<'info> {
+    // the player pays any transfer fee on top so `amount` is what should land
+    fn deposit(
+        &self,
+        from: AccountInfo<'info>,
+        owner: AccountInfo<'info>,
+        amount: u64,
+    ) -> Result<u64> {
+        let balance_before = self.balance()?;
+
+        // native mints can be paid in with lamports straight from the wallet
+        if token_interface::is_wallet(&from) {
+            if !token_interface::is_native_mint(self.mint.key) {
+                return Err(ProgramError::InvalidArgument.into());
+            }
+            anchor_lang::system_program::transfer(
+                CpiContext::new(
+                    self.system_program.clone(),
+                    anchor_lang::system_program::Transfer {
+                        from,
+                        to: self.escrow_token_account.clone(),
+                    },
+                ),
+                amount,
+            )?;
+            invoke(
+                &spl_token_2022::instruction::sync_native(
+                    self.token_program.key,
+                    self.escrow_token_account.key,
+                )?,
+                &[
+                    self.escrow_token_account.clone(),
+                    self.token_program.clone(),
+                ],
+            )?;
+            return Ok(self.balance()?.saturating_sub(balance_before));
+        }
+
+        let mint_info = self.mint_info()?;
+        let pre_fee_amount = mint_info
+            .pre_fee_amount(amount, Clock::get()?.epoch)
+            .ok_or(ProgramError::InvalidArgument)?;
+
+        invoke(
+            &spl_token_2022::instruction::transfer_checked(
+                self.token_program.key,
+                from.key,
+                self.mint.key,
+                self.escrow_token_account.key,
+                owner.key,
+                &[],
+                pre_fee_amount,
+                mint_info.decimals,
+            )?,
+            &[
+                from,
+                self.mint.clone(),
+                self.escrow_token_account.clone(),
+                owner,
+                self.token_program.clone(),
+            ],
+        )?;
+
+        Ok(self.balance()?.saturating_sub(balance_before))
+    }
+
+    fn withdraw(
+        &self,
+        to: AccountInfo<'info>,
+        amount: u64,
+        signer_seeds: &[&[&[u8]]],
+    ) -> Result<()> {
+        invoke_signed(
+            &spl_token_2022::instruction::transfer_checked(
+                self.token_program.key,
+                self.escrow_token_account.key,
+                self.mint.key,
+                to.key,
+                self.game_authority.key,
+                &[],
+                amount,
+                self.mint_info()?.decimals,
+            )?,
+            &[
+                self.escrow_token_account.clone(),
+                self.mint.clone(),
+                to,
+                self.game_authority.clone(),
+                self.token_program.clone(),
+            ],
+            signer_seeds,
+        )?;
+        Ok(())
+    }
+
+    fn close(&self, destination: AccountInfo<'info>, signer_seeds: &[&[&[u8]]]) -> Result<()> {
+        // already closed to pay out wrapped sol
+        if self.escrow_token_account.lamports() == 0 {
+            return Ok(());
+        }
+
+        // harvesting is permissionless and moves the fees to the mint
+        if token_interface::withheld_fees(&self.escrow_token_account).unwrap_or(0) > 0 {
+            invoke(
+                &spl_token_2022::extension::transfer_fee::instruction::harvest_withheld_tokens_to_mint(
+                    self.token_program.key,
+                    self.mint.key,
+                    &[self.escrow_token_account.key],
+                )?,
+                &[
+                    self.mint.clone(),
+                    self.escrow_token_account.clone(),
+                    self.token_program.clone(),
+                ],
+            )?;
+        }
+
+        invoke_signed(
+            &spl_token_2022::instruction::close_account(
+                self.token_program.key,
+                self.escrow_token_account.key,
+                destination.key,
+                self.game_authority.key,
+                &[],
+            )?,
+            &[
+                self.escrow_token_account.clone(),
+                destination,
+                self.game_authority.clone(),
+                self.token_program.clone(),
+            ],
+            signer_seeds,
+        )?;
+        Ok(())
+    }
+}
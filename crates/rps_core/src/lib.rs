@@ -0,0 +1,9 @@
+#![allow(clippy::result_large_err)]
+//! Everything `rps` and `rps_token` have in common: the game state machine,
+//! the game and player data their accounts wrap and the escrow the wagers
+//! sit in. The programs only add their accounts, fees and which escrow to use.
+
+pub mod escrow;
+pub mod logic;
+pub mod state;
+pub mod token_interface;
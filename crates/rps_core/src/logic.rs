@@ -0,0 +1,2416 @@
+use anchor_lang::prelude::*;
+use serde::{Deserialize, Serialize};
+use solana_program::keccak::hashv;
+
+pub fn verify_commitment(pubkey: Pubkey, commitment: [u8; 32], salt: u64, choice: RPS) -> bool {
+    let choice8: u8 = choice.into();
+    let buffer = &[pubkey.as_ref(), &salt.to_le_bytes(), &choice8.to_le_bytes()];
+    let hash = hashv(buffer);
+    hash.0 == commitment
+}
+
+// tags v2 commitments so a hash made for anything else can't pass as one
+const COMMITMENT_DOMAIN: &[u8] = b"rps-commitment-v2";
+
+/// v2 commitments are tied to the program and game they were made for, so
+/// the same commitment can't be replayed in another game, and use a 32 byte
+/// salt.
+pub fn verify_commitment_v2(
+    program_id: Pubkey,
+    game: Pubkey,
+    pubkey: Pubkey,
+    commitment: [u8; 32],
+    salt: [u8; 32],
+    choice: RPS,
+) -> bool {
+    let choice8: u8 = choice.into();
+    let hash = hashv(&[
+        COMMITMENT_DOMAIN,
+        program_id.as_ref(),
+        game.as_ref(),
+        pubkey.as_ref(),
+        &salt,
+        &[choice8],
+    ]);
+    hash.0 == commitment
+}
+
+pub fn verify_entry(pubkey: Pubkey, entry_proof: [u8; 32], secret: u64) -> bool {
+    let hash = hashv(&[pubkey.as_ref(), &secret.to_le_bytes()]);
+    hash.0 == entry_proof
+}
+
+/// Every move across all move sets. The u8 encoding used in commitments is
+/// the variant index so new moves must only ever be appended.
+#[derive(
+    Debug, PartialEq, Eq, Clone, Copy, AnchorSerialize, AnchorDeserialize, Serialize, Deserialize,
+)]
+pub enum RPS {
+    Rock,
+    Paper,
+    Scissors,
+    Lizard,
+    Spock,
+}
+
+impl From<RPS> for u8 {
+    fn from(rps: RPS) -> Self {
+        match rps {
+            RPS::Rock => 0,
+            RPS::Paper => 1,
+            RPS::Scissors => 2,
+            RPS::Lizard => 3,
+            RPS::Spock => 4,
+        }
+    }
+}
+
+impl TryFrom<u8> for RPS {
+    type Error = TransitionError;
+
+    fn try_from(choice: u8) -> std::result::Result<Self, Self::Error> {
+        match choice {
+            0 => Ok(RPS::Rock),
+            1 => Ok(RPS::Paper),
+            2 => Ok(RPS::Scissors),
+            3 => Ok(RPS::Lizard),
+            4 => Ok(RPS::Spock),
+            _ => Err(TransitionError::InvalidMove),
+        }
+    }
+}
+
+// DOMINANCE[a][b] is true when move a beats move b, indexed by the u8
+// encoding. Smaller move sets use the top left corner of the table.
+const DOMINANCE: [[bool; 5]; 5] = [
+    // rock crushes scissors and lizard
+    [false, false, true, true, false],
+    // paper covers rock and disproves spock
+    [true, false, false, false, true],
+    // scissors cuts paper and decapitates lizard
+    [false, true, false, true, false],
+    // lizard eats paper and poisons spock
+    [false, true, false, false, true],
+    // spock vaporizes rock and smashes scissors
+    [true, false, true, false, false],
+];
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, AnchorSerialize, AnchorDeserialize)]
+pub enum MoveSet {
+    // rock, paper, scissors
+    Classic,
+    // rock, paper, scissors, lizard, spock
+    Rpsls,
+}
+
+impl MoveSet {
+    pub fn size(self) -> u8 {
+        match self {
+            MoveSet::Classic => 3,
+            MoveSet::Rpsls => 5,
+        }
+    }
+    pub fn contains(self, choice: RPS) -> bool {
+        u8::from(choice) < self.size()
+    }
+    pub fn round_winner(self, player_1_choice: RPS, player_2_choice: RPS) -> Winner {
+        let p1 = u8::from(player_1_choice) as usize;
+        let p2 = u8::from(player_2_choice) as usize;
+        if DOMINANCE[p1][p2] {
+            Winner::P1
+        } else if DOMINANCE[p2][p1] {
+            Winner::P2
+        } else {
+            Winner::TIE
+        }
+    }
+}
+
+#[derive(
+    Debug, PartialEq, Eq, Clone, Copy, AnchorSerialize, AnchorDeserialize, Serialize, Deserialize,
+)]
+pub enum Winner {
+    P1,
+    P2,
+    TIE,
+}
+impl From<Winner> for u8 {
+    fn from(result: Winner) -> Self {
+        match result {
+            Winner::P1 => 0,
+            Winner::P2 => 1,
+            Winner::TIE => 2,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, AnchorSerialize, AnchorDeserialize)]
+pub enum PlayerState {
+    Committed {
+        pubkey: Pubkey,
+        commitment: [u8; 32],
+    },
+    Revealed {
+        pubkey: Pubkey,
+        choice: RPS,
+    },
+}
+
+impl PlayerState {
+    pub fn pubkey(self) -> Pubkey {
+        match self {
+            PlayerState::Committed { pubkey, .. } => pubkey,
+            PlayerState::Revealed { pubkey, .. } => pubkey,
+        }
+    }
+    pub fn choice_or_unrevealed(self) -> Option<RPS> {
+        match self {
+            PlayerState::Committed { .. } => None,
+            PlayerState::Revealed { choice, .. } => Some(choice),
+        }
+    }
+}
+
+pub const DEFAULT_EXPIRY_SLOTS: u64 = 2 * 60 * 5;
+pub const MIN_EXPIRY_SLOTS: u64 = 2 * 10;
+pub const MAX_EXPIRY_SLOTS: u64 = 2 * 60 * 60 * 24;
+pub const MAX_BEST_OF: u8 = 7;
+// a match that keeps tying ends in a draw instead of replaying forever
+pub const MAX_TIED_ROUNDS: u8 = 100;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, AnchorSerialize, AnchorDeserialize)]
+pub struct GameConfig {
+    pub entry_proof: Option<[u8; 32]>,
+    // slots player 2 has to join after the game is created
+    pub challenge_duration: u64,
+    // slots player 1 has to reveal after player 2 joins, also used for each
+    // move in later rounds of a match
+    pub reveal_duration: u64,
+    // number of rounds in a match, 1 for a single throw
+    pub best_of: u8,
+    pub commit_mode: CommitMode,
+    pub move_set: MoveSet,
+    pub commitment_version: CommitmentVersion,
+}
+
+impl GameConfig {
+    pub fn durations_in_bounds(&self) -> bool {
+        (MIN_EXPIRY_SLOTS..=MAX_EXPIRY_SLOTS).contains(&self.challenge_duration)
+            && (MIN_EXPIRY_SLOTS..=MAX_EXPIRY_SLOTS).contains(&self.reveal_duration)
+    }
+    pub fn best_of_valid(&self) -> bool {
+        self.best_of % 2 == 1 && self.best_of <= MAX_BEST_OF
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, AnchorSerialize, AnchorDeserialize)]
+pub enum CommitMode {
+    // player 2 plays their choice in the open and only player 1 reveals
+    Player1,
+    // player 2 also commits and both players reveal, whoever doesn't reveal
+    // loses on expiry
+    BothPlayers,
+}
+
+/// How every commitment in a game is hashed. V1 is `keccak(pubkey, salt,
+/// choice)` with a u64 salt and is only kept for existing clients.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, AnchorSerialize, AnchorDeserialize)]
+pub enum CommitmentVersion {
+    V1,
+    V2,
+}
+
+/// Revealed salt, has to match the game's commitment version.
+#[derive(
+    Debug, PartialEq, Eq, Clone, Copy, AnchorSerialize, AnchorDeserialize, Serialize, Deserialize,
+)]
+#[serde(untagged)]
+pub enum Salt {
+    V1(u64),
+    V2([u8; 32]),
+}
+
+/// Rounds won by each player so far, tied rounds get replayed up to
+/// `MAX_TIED_ROUNDS` times.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, AnchorSerialize, AnchorDeserialize)]
+pub struct Score {
+    pub player_1: u8,
+    pub player_2: u8,
+    pub ties: u8,
+}
+
+impl Score {
+    pub fn record(self, round: Winner) -> Score {
+        match round {
+            Winner::P1 => Score {
+                player_1: self.player_1 + 1,
+                ..self
+            },
+            Winner::P2 => Score {
+                player_2: self.player_2 + 1,
+                ..self
+            },
+            Winner::TIE => Score {
+                ties: self.ties.saturating_add(1),
+                ..self
+            },
+        }
+    }
+    pub fn match_winner(self, best_of: u8) -> Option<Winner> {
+        let majority = best_of / 2 + 1;
+        if self.player_1 >= majority {
+            Some(Winner::P1)
+        } else if self.player_2 >= majority {
+            Some(Winner::P2)
+        } else if self.ties >= MAX_TIED_ROUNDS {
+            Some(Winner::TIE)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, AnchorSerialize, AnchorDeserialize)]
+pub enum GameState {
+    Initialized,
+    AcceptingChallenge {
+        config: GameConfig,
+        player_1: PlayerState,
+        expiry_slot: u64,
+    },
+    AcceptingReveal {
+        player_1: PlayerState,
+        player_2: PlayerState,
+        config: GameConfig,
+        score: Score,
+        expiry_slot: u64,
+    },
+    // match only, waiting on player 1 to commit to the next round
+    AcceptingCommit {
+        player_1: PlayerState,
+        player_2: PlayerState,
+        config: GameConfig,
+        score: Score,
+        expiry_slot: u64,
+    },
+    // match only, waiting on player 2 to play the next round
+    AcceptingChoice {
+        player_1: PlayerState,
+        player_2: PlayerState,
+        config: GameConfig,
+        score: Score,
+        expiry_slot: u64,
+    },
+    AcceptingSettle {
+        result: Winner,
+        player_1: PlayerState,
+        player_2: PlayerState,
+        config: GameConfig,
+        score: Score,
+    },
+    Settled {
+        result: Winner,
+        player_1: PlayerState,
+        player_2: PlayerState,
+        config: GameConfig,
+        score: Score,
+    },
+    // player 1 withdrew before anyone joined
+    Cancelled {
+        player_1: PlayerState,
+        config: GameConfig,
+    },
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, AnchorSerialize, AnchorDeserialize)]
+pub enum Actions {
+    CreateGame {
+        player_1_pubkey: Pubkey,
+        commitment: [u8; 32],
+        config: GameConfig,
+    },
+    JoinGame {
+        player_2_pubkey: Pubkey,
+        choice: RPS,
+        secret: Option<u64>,
+    },
+    JoinGameCommitted {
+        player_2_pubkey: Pubkey,
+        commitment: [u8; 32],
+        secret: Option<u64>,
+    },
+    Reveal {
+        player_pubkey: Pubkey,
+        salt: Salt,
+        choice: RPS,
+    },
+    CommitRound {
+        player_1_pubkey: Pubkey,
+        commitment: [u8; 32],
+    },
+    PlayRound {
+        player_2_pubkey: Pubkey,
+        choice: RPS,
+    },
+    PlayRoundCommitted {
+        player_2_pubkey: Pubkey,
+        commitment: [u8; 32],
+    },
+    ExpireGame {
+        player_pubkey: Pubkey,
+    },
+    CancelGame {
+        player_pubkey: Pubkey,
+    },
+    Settle,
+}
+
+/// Reasons `process_action` can reject an action, one per rule of the game.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TransitionError {
+    InvalidExpiryDuration,
+    InvalidBestOf,
+    WrongCommitMode,
+    ChallengeExpired,
+    RoundExpired,
+    InvalidEntrySecret,
+    NotExpired,
+    OnlyPlayer1CanExpireUnmatched,
+    OnlyPlayer1CanExpireUnrevealed,
+    OnlyPlayer2CanExpireUnrevealed,
+    OnlyPlayer1CanExpireUnplayed,
+    OnlyPlayer2CanExpireUncommitted,
+    NotPlayer1,
+    NotPlayer2,
+    NotInGame,
+    InvalidCommitment,
+    InvalidMove,
+    InvalidTransition,
+}
+
+fn check_join(
+    state_pubkey: Pubkey,
+    config: GameConfig,
+    expiry_slot: u64,
+    secret: Option<u64>,
+    slot: u64,
+) -> std::result::Result<(), TransitionError> {
+    if slot > expiry_slot {
+        return Err(TransitionError::ChallengeExpired);
+    }
+    if let Some(entry_proof) = config.entry_proof {
+        if let Some(secret) = secret {
+            if !verify_entry(state_pubkey, entry_proof, secret) {
+                return Err(TransitionError::InvalidEntrySecret);
+            }
+        } else {
+            return Err(TransitionError::InvalidEntrySecret);
+        }
+    }
+    Ok(())
+}
+
+fn check_commitment(
+    program_id: Pubkey,
+    game: Pubkey,
+    config: GameConfig,
+    pubkey: Pubkey,
+    commitment: [u8; 32],
+    salt: Salt,
+    choice: RPS,
+) -> bool {
+    match (config.commitment_version, salt) {
+        (CommitmentVersion::V1, Salt::V1(salt)) => {
+            verify_commitment(pubkey, commitment, salt, choice)
+        }
+        (CommitmentVersion::V2, Salt::V2(salt)) => {
+            verify_commitment_v2(program_id, game, pubkey, commitment, salt, choice)
+        }
+        _ => false,
+    }
+}
+
+// scores a round once both choices are known and either settles the game or
+// moves on to the next round of the match
+fn finish_round(
+    player_1: (Pubkey, RPS),
+    player_2: (Pubkey, RPS),
+    config: GameConfig,
+    score: Score,
+    slot: u64,
+) -> GameState {
+    let round = config.move_set.round_winner(player_1.1, player_2.1);
+    let score = score.record(round);
+    let player_1 = PlayerState::Revealed {
+        pubkey: player_1.0,
+        choice: player_1.1,
+    };
+    let player_2 = PlayerState::Revealed {
+        pubkey: player_2.0,
+        choice: player_2.1,
+    };
+    // a single throw settles on the first reveal even if tied
+    let result = if config.best_of == 1 {
+        Some(round)
+    } else {
+        score.match_winner(config.best_of)
+    };
+    match result {
+        Some(result) => GameState::AcceptingSettle {
+            result,
+            player_1,
+            player_2,
+            config,
+            score,
+        },
+        None => GameState::AcceptingCommit {
+            player_1,
+            player_2,
+            config,
+            score,
+            expiry_slot: slot + config.reveal_duration,
+        },
+    }
+}
+
+pub fn process_action(
+    program_id: Pubkey,
+    state_pubkey: Pubkey,
+    state: GameState,
+    action: Actions,
+    slot: u64,
+) -> std::result::Result<GameState, TransitionError> {
+    let next_state = match (state, action) {
+        (
+            GameState::Initialized,
+            Actions::CreateGame {
+                player_1_pubkey: pubkey,
+                commitment,
+                config,
+            },
+        ) => {
+            // msg!("{:?}", commitment);
+            if !config.durations_in_bounds() {
+                return Err(TransitionError::InvalidExpiryDuration);
+            }
+            if !config.best_of_valid() {
+                return Err(TransitionError::InvalidBestOf);
+            }
+            GameState::AcceptingChallenge {
+                config,
+                player_1: PlayerState::Committed { pubkey, commitment },
+                expiry_slot: slot + config.challenge_duration,
+            }
+        }
+
+        (
+            GameState::AcceptingChallenge {
+                player_1,
+                config,
+                expiry_slot,
+            },
+            Actions::JoinGame {
+                player_2_pubkey,
+                choice,
+                secret,
+            },
+        ) => {
+            if config.commit_mode != CommitMode::Player1 {
+                return Err(TransitionError::WrongCommitMode);
+            }
+            if !config.move_set.contains(choice) {
+                return Err(TransitionError::InvalidMove);
+            }
+            check_join(state_pubkey, config, expiry_slot, secret, slot)?;
+            GameState::AcceptingReveal {
+                player_1,
+                player_2: PlayerState::Revealed {
+                    pubkey: player_2_pubkey,
+                    choice,
+                },
+                config,
+                score: Score::default(),
+                expiry_slot: slot + config.reveal_duration,
+            }
+        }
+        (
+            GameState::AcceptingChallenge {
+                player_1,
+                config,
+                expiry_slot,
+            },
+            Actions::JoinGameCommitted {
+                player_2_pubkey,
+                commitment,
+                secret,
+            },
+        ) => {
+            if config.commit_mode != CommitMode::BothPlayers {
+                return Err(TransitionError::WrongCommitMode);
+            }
+            check_join(state_pubkey, config, expiry_slot, secret, slot)?;
+            GameState::AcceptingReveal {
+                player_1,
+                player_2: PlayerState::Committed {
+                    pubkey: player_2_pubkey,
+                    commitment,
+                },
+                config,
+                score: Score::default(),
+                expiry_slot: slot + config.reveal_duration,
+            }
+        }
+        (
+            GameState::AcceptingChallenge {
+                player_1:
+                    PlayerState::Committed {
+                        pubkey: p1,
+                        commitment: player_1_commitment,
+                    },
+                config,
+                expiry_slot,
+            },
+            Actions::ExpireGame { player_pubkey },
+        ) => {
+            if slot < expiry_slot {
+                return Err(TransitionError::NotExpired);
+            }
+            if player_pubkey != p1 {
+                return Err(TransitionError::OnlyPlayer1CanExpireUnmatched);
+            }
+            GameState::AcceptingSettle {
+                result: Winner::P1,
+                player_1: PlayerState::Committed {
+                    pubkey: p1,
+                    commitment: player_1_commitment,
+                },
+                player_2: PlayerState::Committed {
+                    pubkey: p1,
+                    commitment: player_1_commitment,
+                },
+                config,
+                score: Score::default(),
+            }
+        }
+
+        (
+            GameState::AcceptingChallenge {
+                player_1, config, ..
+            },
+            Actions::CancelGame { player_pubkey },
+        ) => {
+            if player_pubkey != player_1.pubkey() {
+                return Err(TransitionError::NotPlayer1);
+            }
+            GameState::Cancelled { player_1, config }
+        }
+
+        (
+            GameState::AcceptingReveal {
+                player_1:
+                    PlayerState::Committed {
+                        pubkey: p1,
+                        commitment: player_1_commitment,
+                    },
+                player_2:
+                    PlayerState::Revealed {
+                        pubkey: p2,
+                        choice: player_2_choice,
+                    },
+                config,
+                score,
+                expiry_slot: _,
+            },
+            Actions::Reveal {
+                player_pubkey,
+                salt,
+                choice,
+            },
+        ) => {
+            if p1 != player_pubkey {
+                return Err(TransitionError::NotPlayer1);
+            }
+            if !check_commitment(
+                program_id,
+                state_pubkey,
+                config,
+                p1,
+                player_1_commitment,
+                salt,
+                choice,
+            ) {
+                return Err(TransitionError::InvalidCommitment);
+            }
+            if !config.move_set.contains(choice) {
+                return Err(TransitionError::InvalidMove);
+            }
+            finish_round((p1, choice), (p2, player_2_choice), config, score, slot)
+        }
+        (
+            GameState::AcceptingReveal {
+                player_1:
+                    PlayerState::Revealed {
+                        pubkey: p1,
+                        choice: player_1_choice,
+                    },
+                player_2:
+                    PlayerState::Committed {
+                        pubkey: p2,
+                        commitment: player_2_commitment,
+                    },
+                config,
+                score,
+                expiry_slot: _,
+            },
+            Actions::Reveal {
+                player_pubkey,
+                salt,
+                choice,
+            },
+        ) => {
+            if p2 != player_pubkey {
+                return Err(TransitionError::NotPlayer2);
+            }
+            if !check_commitment(
+                program_id,
+                state_pubkey,
+                config,
+                p2,
+                player_2_commitment,
+                salt,
+                choice,
+            ) {
+                return Err(TransitionError::InvalidCommitment);
+            }
+            if !config.move_set.contains(choice) {
+                return Err(TransitionError::InvalidMove);
+            }
+            finish_round((p1, player_1_choice), (p2, choice), config, score, slot)
+        }
+        (
+            GameState::AcceptingReveal {
+                player_1:
+                    player_1 @ PlayerState::Committed {
+                        pubkey: p1,
+                        commitment: player_1_commitment,
+                    },
+                player_2:
+                    player_2 @ PlayerState::Committed {
+                        pubkey: p2,
+                        commitment: player_2_commitment,
+                    },
+                config,
+                score,
+                expiry_slot,
+            },
+            Actions::Reveal {
+                player_pubkey,
+                salt,
+                choice,
+            },
+        ) => {
+            // both players committed, whoever reveals first waits on the other
+            let (commitment, player_1, player_2) = if player_pubkey == p1 {
+                (
+                    player_1_commitment,
+                    PlayerState::Revealed { pubkey: p1, choice },
+                    player_2,
+                )
+            } else if player_pubkey == p2 {
+                (
+                    player_2_commitment,
+                    player_1,
+                    PlayerState::Revealed { pubkey: p2, choice },
+                )
+            } else {
+                return Err(TransitionError::NotInGame);
+            };
+            if !check_commitment(
+                program_id,
+                state_pubkey,
+                config,
+                player_pubkey,
+                commitment,
+                salt,
+                choice,
+            ) {
+                return Err(TransitionError::InvalidCommitment);
+            }
+            if !config.move_set.contains(choice) {
+                return Err(TransitionError::InvalidMove);
+            }
+            GameState::AcceptingReveal {
+                player_1,
+                player_2,
+                config,
+                score,
+                expiry_slot,
+            }
+        }
+
+        (
+            GameState::AcceptingReveal {
+                player_1,
+                player_2:
+                    PlayerState::Revealed {
+                        pubkey: p2,
+                        choice: player_2_choice,
+                    },
+                config,
+                score,
+                expiry_slot,
+            },
+            Actions::ExpireGame { player_pubkey },
+        ) => {
+            if slot < expiry_slot {
+                return Err(TransitionError::NotExpired);
+            }
+            if player_pubkey != p2 {
+                return Err(TransitionError::OnlyPlayer2CanExpireUnrevealed);
+            }
+            GameState::AcceptingSettle {
+                result: Winner::P2,
+                player_1,
+                player_2: PlayerState::Revealed {
+                    pubkey: p2,
+                    choice: player_2_choice,
+                },
+                config,
+                score,
+            }
+        }
+        (
+            GameState::AcceptingReveal {
+                player_1: player_1 @ PlayerState::Revealed { pubkey: p1, .. },
+                player_2,
+                config,
+                score,
+                expiry_slot,
+            },
+            Actions::ExpireGame { player_pubkey },
+        ) => {
+            if slot < expiry_slot {
+                return Err(TransitionError::NotExpired);
+            }
+            if player_pubkey != p1 {
+                return Err(TransitionError::OnlyPlayer1CanExpireUnrevealed);
+            }
+            GameState::AcceptingSettle {
+                result: Winner::P1,
+                player_1,
+                player_2,
+                config,
+                score,
+            }
+        }
+        (
+            GameState::AcceptingReveal {
+                player_1,
+                player_2,
+                config,
+                score,
+                expiry_slot,
+            },
+            Actions::ExpireGame { player_pubkey },
+        ) => {
+            // neither player revealed so everyone gets their wager back
+            if slot < expiry_slot {
+                return Err(TransitionError::NotExpired);
+            }
+            if player_pubkey != player_1.pubkey() && player_pubkey != player_2.pubkey() {
+                return Err(TransitionError::NotInGame);
+            }
+            GameState::AcceptingSettle {
+                result: Winner::TIE,
+                player_1,
+                player_2,
+                config,
+                score,
+            }
+        }
+
+        (
+            GameState::AcceptingCommit {
+                player_1,
+                player_2,
+                config,
+                score,
+                expiry_slot,
+            },
+            Actions::CommitRound {
+                player_1_pubkey,
+                commitment,
+            },
+        ) => {
+            if slot > expiry_slot {
+                return Err(TransitionError::RoundExpired);
+            }
+            if player_1.pubkey() != player_1_pubkey {
+                return Err(TransitionError::NotPlayer1);
+            }
+            GameState::AcceptingChoice {
+                player_1: PlayerState::Committed {
+                    pubkey: player_1_pubkey,
+                    commitment,
+                },
+                player_2,
+                config,
+                score,
+                expiry_slot: slot + config.reveal_duration,
+            }
+        }
+        (
+            GameState::AcceptingCommit {
+                player_1,
+                player_2,
+                config,
+                score,
+                expiry_slot,
+            },
+            Actions::ExpireGame { player_pubkey },
+        ) => {
+            if slot < expiry_slot {
+                return Err(TransitionError::NotExpired);
+            }
+            if player_pubkey != player_2.pubkey() {
+                return Err(TransitionError::OnlyPlayer2CanExpireUncommitted);
+            }
+            GameState::AcceptingSettle {
+                result: Winner::P2,
+                player_1,
+                player_2,
+                config,
+                score,
+            }
+        }
+
+        (
+            GameState::AcceptingChoice {
+                player_1,
+                player_2,
+                config,
+                score,
+                expiry_slot,
+            },
+            Actions::PlayRound {
+                player_2_pubkey,
+                choice,
+            },
+        ) => {
+            if config.commit_mode != CommitMode::Player1 {
+                return Err(TransitionError::WrongCommitMode);
+            }
+            if slot > expiry_slot {
+                return Err(TransitionError::RoundExpired);
+            }
+            if player_2.pubkey() != player_2_pubkey {
+                return Err(TransitionError::NotPlayer2);
+            }
+            if !config.move_set.contains(choice) {
+                return Err(TransitionError::InvalidMove);
+            }
+            GameState::AcceptingReveal {
+                player_1,
+                player_2: PlayerState::Revealed {
+                    pubkey: player_2_pubkey,
+                    choice,
+                },
+                config,
+                score,
+                expiry_slot: slot + config.reveal_duration,
+            }
+        }
+        (
+            GameState::AcceptingChoice {
+                player_1,
+                player_2,
+                config,
+                score,
+                expiry_slot,
+            },
+            Actions::PlayRoundCommitted {
+                player_2_pubkey,
+                commitment,
+            },
+        ) => {
+            if config.commit_mode != CommitMode::BothPlayers {
+                return Err(TransitionError::WrongCommitMode);
+            }
+            if slot > expiry_slot {
+                return Err(TransitionError::RoundExpired);
+            }
+            if player_2.pubkey() != player_2_pubkey {
+                return Err(TransitionError::NotPlayer2);
+            }
+            GameState::AcceptingReveal {
+                player_1,
+                player_2: PlayerState::Committed {
+                    pubkey: player_2_pubkey,
+                    commitment,
+                },
+                config,
+                score,
+                expiry_slot: slot + config.reveal_duration,
+            }
+        }
+        (
+            GameState::AcceptingChoice {
+                player_1,
+                player_2,
+                config,
+                score,
+                expiry_slot,
+            },
+            Actions::ExpireGame { player_pubkey },
+        ) => {
+            if slot < expiry_slot {
+                return Err(TransitionError::NotExpired);
+            }
+            if player_pubkey != player_1.pubkey() {
+                return Err(TransitionError::OnlyPlayer1CanExpireUnplayed);
+            }
+            GameState::AcceptingSettle {
+                result: Winner::P1,
+                player_1,
+                player_2,
+                config,
+                score,
+            }
+        }
+
+        (
+            GameState::AcceptingSettle {
+                result,
+                player_1: p1,
+                player_2: p2,
+                config,
+                score,
+            },
+            Actions::Settle,
+        ) => GameState::Settled {
+            result,
+            player_1: p1,
+            player_2: p2,
+            config,
+            score,
+        },
+
+        _ => return Err(TransitionError::InvalidTransition),
+    };
+    Ok(next_state)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const PROGRAM_ID: Pubkey = Pubkey::new_from_array([7; 32]);
+
+    #[test]
+    fn test_process_action() {
+        let state = GameState::Initialized;
+
+        let state_pubkey = Pubkey::new_unique();
+        let player_1_pubkey = Pubkey::new_unique();
+        let salt = 36;
+        let commitment = create_commitment(player_1_pubkey, salt, RPS::Rock);
+        let player_2_pubkey = Pubkey::new_unique();
+        let slot: u64 = 0;
+        let config = GameConfig {
+            entry_proof: None,
+            challenge_duration: DEFAULT_EXPIRY_SLOTS,
+            reveal_duration: DEFAULT_EXPIRY_SLOTS,
+            best_of: 1,
+            commit_mode: CommitMode::Player1,
+            move_set: MoveSet::Classic,
+            commitment_version: CommitmentVersion::V1,
+        };
+
+        let state = {
+            let action = Actions::CreateGame {
+                player_1_pubkey,
+                commitment,
+                config,
+            };
+            let expected = GameState::AcceptingChallenge {
+                config,
+                player_1: PlayerState::Committed {
+                    pubkey: player_1_pubkey,
+                    commitment,
+                },
+                expiry_slot: 600,
+            };
+
+            assert_eq!(
+                process_action(PROGRAM_ID, state_pubkey, state, action, slot),
+                Ok(expected)
+            );
+            expected
+        };
+
+        let state = {
+            let action = Actions::JoinGame {
+                player_2_pubkey,
+                choice: RPS::Paper,
+                secret: None,
+            };
+            let expected = GameState::AcceptingReveal {
+                player_1: PlayerState::Committed {
+                    pubkey: player_1_pubkey,
+                    commitment,
+                },
+                player_2: PlayerState::Revealed {
+                    pubkey: player_2_pubkey,
+                    choice: RPS::Paper,
+                },
+                config,
+                score: Score::default(),
+                expiry_slot: 600,
+            };
+            assert_eq!(
+                process_action(PROGRAM_ID, state_pubkey, state, action, slot),
+                Ok(expected)
+            );
+            expected
+        };
+
+        let state = {
+            let action = Actions::Reveal {
+                player_pubkey: player_1_pubkey,
+                salt: Salt::V1(salt),
+                choice: RPS::Rock,
+            };
+            let expected = GameState::AcceptingSettle {
+                result: Winner::P2,
+                player_1: PlayerState::Revealed {
+                    pubkey: player_1_pubkey,
+                    choice: RPS::Rock,
+                },
+                player_2: PlayerState::Revealed {
+                    pubkey: player_2_pubkey,
+                    choice: RPS::Paper,
+                },
+                config,
+                score: Score {
+                    player_1: 0,
+                    player_2: 1,
+                    ties: 0,
+                },
+            };
+            assert_eq!(
+                process_action(PROGRAM_ID, state_pubkey, state, action, slot),
+                Ok(expected)
+            );
+            expected
+        };
+
+        let _state = {
+            let action = Actions::Settle;
+            let expected = GameState::Settled {
+                result: Winner::P2,
+                player_1: PlayerState::Revealed {
+                    pubkey: player_1_pubkey,
+                    choice: RPS::Rock,
+                },
+                player_2: PlayerState::Revealed {
+                    pubkey: player_2_pubkey,
+                    choice: RPS::Paper,
+                },
+                config,
+                score: Score {
+                    player_1: 0,
+                    player_2: 1,
+                    ties: 0,
+                },
+            };
+            assert_eq!(
+                process_action(PROGRAM_ID, state_pubkey, state, action, slot),
+                Ok(expected)
+            );
+            expected
+        };
+    }
+
+    #[test]
+    fn test_process_action_with_secret() {
+        let state = GameState::Initialized;
+
+        let state_pubkey = Pubkey::new_unique();
+        let player_1_pubkey = Pubkey::new_unique();
+        let salt = 36;
+        let commitment = create_commitment(player_1_pubkey, salt, RPS::Rock);
+        let player_2_pubkey = Pubkey::new_unique();
+        let slot: u64 = 0;
+        let secret = Some(8238538u64);
+        let entry_proof = Some(create_entry_proof(state_pubkey, 8238538u64));
+        let config = GameConfig {
+            entry_proof,
+            challenge_duration: DEFAULT_EXPIRY_SLOTS,
+            reveal_duration: DEFAULT_EXPIRY_SLOTS,
+            best_of: 1,
+            commit_mode: CommitMode::Player1,
+            move_set: MoveSet::Classic,
+            commitment_version: CommitmentVersion::V1,
+        };
+
+        let state = {
+            let action = Actions::CreateGame {
+                player_1_pubkey,
+                commitment,
+                config,
+            };
+            let expected = GameState::AcceptingChallenge {
+                config,
+                player_1: PlayerState::Committed {
+                    pubkey: player_1_pubkey,
+                    commitment,
+                },
+                expiry_slot: 600,
+            };
+
+            assert_eq!(
+                process_action(PROGRAM_ID, state_pubkey, state, action, slot),
+                Ok(expected)
+            );
+            expected
+        };
+
+        let state = {
+            let action = Actions::JoinGame {
+                player_2_pubkey,
+                choice: RPS::Paper,
+                secret,
+            };
+            let expected = GameState::AcceptingReveal {
+                player_1: PlayerState::Committed {
+                    pubkey: player_1_pubkey,
+                    commitment,
+                },
+                player_2: PlayerState::Revealed {
+                    pubkey: player_2_pubkey,
+                    choice: RPS::Paper,
+                },
+                config,
+                score: Score::default(),
+                expiry_slot: 600,
+            };
+            assert_eq!(
+                process_action(PROGRAM_ID, state_pubkey, state, action, slot),
+                Ok(expected)
+            );
+            expected
+        };
+
+        let _state = {
+            let action = Actions::Reveal {
+                player_pubkey: player_1_pubkey,
+                salt: Salt::V1(salt),
+                choice: RPS::Rock,
+            };
+            let expected = GameState::AcceptingSettle {
+                result: Winner::P2,
+                player_1: PlayerState::Revealed {
+                    pubkey: player_1_pubkey,
+                    choice: RPS::Rock,
+                },
+                player_2: PlayerState::Revealed {
+                    pubkey: player_2_pubkey,
+                    choice: RPS::Paper,
+                },
+                config,
+                score: Score {
+                    player_1: 0,
+                    player_2: 1,
+                    ties: 0,
+                },
+            };
+            assert_eq!(
+                process_action(PROGRAM_ID, state_pubkey, state, action, slot),
+                Ok(expected)
+            );
+            expected
+        };
+    }
+
+    #[test]
+    fn test_process_action_rejections() {
+        let state_pubkey = Pubkey::new_unique();
+        let player_1_pubkey = Pubkey::new_unique();
+        let player_2_pubkey = Pubkey::new_unique();
+        let salt = 36;
+        let commitment = create_commitment(player_1_pubkey, salt, RPS::Rock);
+        let entry_proof = Some(create_entry_proof(state_pubkey, 8238538u64));
+
+        let accepting_challenge = GameState::AcceptingChallenge {
+            config: GameConfig {
+                entry_proof,
+                challenge_duration: DEFAULT_EXPIRY_SLOTS,
+                reveal_duration: DEFAULT_EXPIRY_SLOTS,
+                best_of: 1,
+                commit_mode: CommitMode::Player1,
+                move_set: MoveSet::Classic,
+                commitment_version: CommitmentVersion::V1,
+            },
+            player_1: PlayerState::Committed {
+                pubkey: player_1_pubkey,
+                commitment,
+            },
+            expiry_slot: 600,
+        };
+        let join = |secret| Actions::JoinGame {
+            player_2_pubkey,
+            choice: RPS::Paper,
+            secret,
+        };
+        assert_eq!(
+            process_action(
+                PROGRAM_ID,
+                state_pubkey,
+                accepting_challenge,
+                join(Some(8238538)),
+                601
+            ),
+            Err(TransitionError::ChallengeExpired)
+        );
+        assert_eq!(
+            process_action(
+                PROGRAM_ID,
+                state_pubkey,
+                accepting_challenge,
+                join(Some(1)),
+                0
+            ),
+            Err(TransitionError::InvalidEntrySecret)
+        );
+        assert_eq!(
+            process_action(PROGRAM_ID, state_pubkey, accepting_challenge, join(None), 0),
+            Err(TransitionError::InvalidEntrySecret)
+        );
+        assert_eq!(
+            process_action(
+                PROGRAM_ID,
+                state_pubkey,
+                accepting_challenge,
+                Actions::ExpireGame {
+                    player_pubkey: player_1_pubkey
+                },
+                599
+            ),
+            Err(TransitionError::NotExpired)
+        );
+        assert_eq!(
+            process_action(
+                PROGRAM_ID,
+                state_pubkey,
+                accepting_challenge,
+                Actions::ExpireGame {
+                    player_pubkey: player_2_pubkey
+                },
+                600
+            ),
+            Err(TransitionError::OnlyPlayer1CanExpireUnmatched)
+        );
+
+        let accepting_reveal = process_action(
+            PROGRAM_ID,
+            state_pubkey,
+            accepting_challenge,
+            join(Some(8238538)),
+            0,
+        )
+        .unwrap();
+        assert_eq!(
+            process_action(
+                PROGRAM_ID,
+                state_pubkey,
+                accepting_reveal,
+                Actions::Reveal {
+                    player_pubkey: player_2_pubkey,
+                    salt: Salt::V1(salt),
+                    choice: RPS::Rock,
+                },
+                0
+            ),
+            Err(TransitionError::NotPlayer1)
+        );
+        assert_eq!(
+            process_action(
+                PROGRAM_ID,
+                state_pubkey,
+                accepting_reveal,
+                Actions::Reveal {
+                    player_pubkey: player_1_pubkey,
+                    salt: Salt::V1(salt + 1),
+                    choice: RPS::Rock,
+                },
+                0
+            ),
+            Err(TransitionError::InvalidCommitment)
+        );
+        assert_eq!(
+            process_action(
+                PROGRAM_ID,
+                state_pubkey,
+                accepting_reveal,
+                Actions::ExpireGame {
+                    player_pubkey: player_1_pubkey
+                },
+                600
+            ),
+            Err(TransitionError::OnlyPlayer2CanExpireUnrevealed)
+        );
+        assert_eq!(
+            process_action(
+                PROGRAM_ID,
+                state_pubkey,
+                accepting_reveal,
+                Actions::Settle,
+                0
+            ),
+            Err(TransitionError::InvalidTransition)
+        );
+    }
+
+    #[test]
+    fn test_process_action_custom_durations() {
+        let state_pubkey = Pubkey::new_unique();
+        let player_1_pubkey = Pubkey::new_unique();
+        let player_2_pubkey = Pubkey::new_unique();
+        let commitment = create_commitment(player_1_pubkey, 36, RPS::Rock);
+        let config = GameConfig {
+            entry_proof: None,
+            challenge_duration: 5000,
+            reveal_duration: MIN_EXPIRY_SLOTS,
+            best_of: 1,
+            commit_mode: CommitMode::Player1,
+            move_set: MoveSet::Classic,
+            commitment_version: CommitmentVersion::V1,
+        };
+
+        let state = process_action(
+            PROGRAM_ID,
+            state_pubkey,
+            GameState::Initialized,
+            Actions::CreateGame {
+                player_1_pubkey,
+                commitment,
+                config,
+            },
+            100,
+        )
+        .unwrap();
+        assert!(matches!(
+            state,
+            GameState::AcceptingChallenge {
+                expiry_slot: 5100,
+                ..
+            }
+        ));
+
+        let state = process_action(
+            PROGRAM_ID,
+            state_pubkey,
+            state,
+            Actions::JoinGame {
+                player_2_pubkey,
+                choice: RPS::Paper,
+                secret: None,
+            },
+            5100,
+        )
+        .unwrap();
+        assert!(matches!(
+            state,
+            GameState::AcceptingReveal {
+                expiry_slot: 5120,
+                ..
+            }
+        ));
+
+        for (challenge_duration, reveal_duration) in [
+            (MIN_EXPIRY_SLOTS - 1, DEFAULT_EXPIRY_SLOTS),
+            (DEFAULT_EXPIRY_SLOTS, MAX_EXPIRY_SLOTS + 1),
+        ] {
+            assert_eq!(
+                process_action(
+                    PROGRAM_ID,
+                    state_pubkey,
+                    GameState::Initialized,
+                    Actions::CreateGame {
+                        player_1_pubkey,
+                        commitment,
+                        config: GameConfig {
+                            entry_proof: None,
+                            challenge_duration,
+                            reveal_duration,
+                            best_of: 1,
+                            commit_mode: CommitMode::Player1,
+                            move_set: MoveSet::Classic,
+                            commitment_version: CommitmentVersion::V1,
+                        },
+                    },
+                    100,
+                ),
+                Err(TransitionError::InvalidExpiryDuration)
+            );
+        }
+    }
+
+    #[test]
+    fn test_process_action_best_of_3() {
+        let state_pubkey = Pubkey::new_unique();
+        let player_1_pubkey = Pubkey::new_unique();
+        let player_2_pubkey = Pubkey::new_unique();
+        let config = GameConfig {
+            entry_proof: None,
+            challenge_duration: DEFAULT_EXPIRY_SLOTS,
+            reveal_duration: DEFAULT_EXPIRY_SLOTS,
+            best_of: 3,
+            commit_mode: CommitMode::Player1,
+            move_set: MoveSet::Classic,
+            commitment_version: CommitmentVersion::V1,
+        };
+
+        let state = process_action(
+            PROGRAM_ID,
+            state_pubkey,
+            GameState::Initialized,
+            Actions::CreateGame {
+                player_1_pubkey,
+                commitment: create_commitment(player_1_pubkey, 1, RPS::Rock),
+                config,
+            },
+            0,
+        )
+        .unwrap();
+        assert_eq!(
+            process_action(
+                PROGRAM_ID,
+                state_pubkey,
+                GameState::Initialized,
+                Actions::CreateGame {
+                    player_1_pubkey,
+                    commitment: [0; 32],
+                    config: GameConfig {
+                        best_of: 2,
+                        ..config
+                    },
+                },
+                0,
+            ),
+            Err(TransitionError::InvalidBestOf)
+        );
+        let state = process_action(
+            PROGRAM_ID,
+            state_pubkey,
+            state,
+            Actions::JoinGame {
+                player_2_pubkey,
+                choice: RPS::Scissors,
+                secret: None,
+            },
+            0,
+        )
+        .unwrap();
+
+        // player 1 wins round 1, round 2 is a tie, player 1 wins round 3
+        let mut state = process_action(
+            PROGRAM_ID,
+            state_pubkey,
+            state,
+            Actions::Reveal {
+                player_pubkey: player_1_pubkey,
+                salt: Salt::V1(1),
+                choice: RPS::Rock,
+            },
+            0,
+        )
+        .unwrap();
+        for (salt, player_1_choice, player_2_choice) in
+            [(2, RPS::Paper, RPS::Paper), (3, RPS::Paper, RPS::Rock)]
+        {
+            assert!(matches!(state, GameState::AcceptingCommit { .. }));
+            assert_eq!(
+                process_action(
+                    PROGRAM_ID,
+                    state_pubkey,
+                    state,
+                    Actions::CommitRound {
+                        player_1_pubkey: player_2_pubkey,
+                        commitment: [0; 32],
+                    },
+                    0,
+                ),
+                Err(TransitionError::NotPlayer1)
+            );
+            state = process_action(
+                PROGRAM_ID,
+                state_pubkey,
+                state,
+                Actions::CommitRound {
+                    player_1_pubkey,
+                    commitment: create_commitment(player_1_pubkey, salt, player_1_choice),
+                },
+                0,
+            )
+            .unwrap();
+            assert_eq!(
+                process_action(
+                    PROGRAM_ID,
+                    state_pubkey,
+                    state,
+                    Actions::PlayRound {
+                        player_2_pubkey: player_1_pubkey,
+                        choice: player_2_choice,
+                    },
+                    0,
+                ),
+                Err(TransitionError::NotPlayer2)
+            );
+            state = process_action(
+                PROGRAM_ID,
+                state_pubkey,
+                state,
+                Actions::PlayRound {
+                    player_2_pubkey,
+                    choice: player_2_choice,
+                },
+                0,
+            )
+            .unwrap();
+            state = process_action(
+                PROGRAM_ID,
+                state_pubkey,
+                state,
+                Actions::Reveal {
+                    player_pubkey: player_1_pubkey,
+                    salt: Salt::V1(salt),
+                    choice: player_1_choice,
+                },
+                0,
+            )
+            .unwrap();
+        }
+
+        assert_eq!(
+            state,
+            GameState::AcceptingSettle {
+                result: Winner::P1,
+                player_1: PlayerState::Revealed {
+                    pubkey: player_1_pubkey,
+                    choice: RPS::Paper,
+                },
+                player_2: PlayerState::Revealed {
+                    pubkey: player_2_pubkey,
+                    choice: RPS::Rock,
+                },
+                config,
+                score: Score {
+                    player_1: 2,
+                    player_2: 0,
+                    ties: 1,
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn test_process_action_best_of_3_expired_rounds() {
+        let state_pubkey = Pubkey::new_unique();
+        let player_1 = PlayerState::Revealed {
+            pubkey: Pubkey::new_unique(),
+            choice: RPS::Rock,
+        };
+        let player_2 = PlayerState::Revealed {
+            pubkey: Pubkey::new_unique(),
+            choice: RPS::Scissors,
+        };
+        let config = GameConfig {
+            entry_proof: None,
+            challenge_duration: DEFAULT_EXPIRY_SLOTS,
+            reveal_duration: DEFAULT_EXPIRY_SLOTS,
+            best_of: 3,
+            commit_mode: CommitMode::Player1,
+            move_set: MoveSet::Classic,
+            commitment_version: CommitmentVersion::V1,
+        };
+        let score = Score {
+            player_1: 1,
+            player_2: 0,
+            ties: 0,
+        };
+
+        // player 1 stalls on committing to the next round
+        let accepting_commit = GameState::AcceptingCommit {
+            player_1,
+            player_2,
+            config,
+            score,
+            expiry_slot: 600,
+        };
+        assert_eq!(
+            process_action(
+                PROGRAM_ID,
+                state_pubkey,
+                accepting_commit,
+                Actions::CommitRound {
+                    player_1_pubkey: player_1.pubkey(),
+                    commitment: [0; 32],
+                },
+                601,
+            ),
+            Err(TransitionError::RoundExpired)
+        );
+        assert_eq!(
+            process_action(
+                PROGRAM_ID,
+                state_pubkey,
+                accepting_commit,
+                Actions::ExpireGame {
+                    player_pubkey: player_1.pubkey(),
+                },
+                600,
+            ),
+            Err(TransitionError::OnlyPlayer2CanExpireUncommitted)
+        );
+        assert_eq!(
+            process_action(
+                PROGRAM_ID,
+                state_pubkey,
+                accepting_commit,
+                Actions::ExpireGame {
+                    player_pubkey: player_2.pubkey(),
+                },
+                600,
+            ),
+            Ok(GameState::AcceptingSettle {
+                result: Winner::P2,
+                player_1,
+                player_2,
+                config,
+                score,
+            })
+        );
+
+        // player 2 stalls on playing the next round
+        let accepting_choice = GameState::AcceptingChoice {
+            player_1,
+            player_2,
+            config,
+            score,
+            expiry_slot: 600,
+        };
+        assert_eq!(
+            process_action(
+                PROGRAM_ID,
+                state_pubkey,
+                accepting_choice,
+                Actions::ExpireGame {
+                    player_pubkey: player_2.pubkey(),
+                },
+                600,
+            ),
+            Err(TransitionError::OnlyPlayer1CanExpireUnplayed)
+        );
+        assert_eq!(
+            process_action(
+                PROGRAM_ID,
+                state_pubkey,
+                accepting_choice,
+                Actions::ExpireGame {
+                    player_pubkey: player_1.pubkey(),
+                },
+                600,
+            ),
+            Ok(GameState::AcceptingSettle {
+                result: Winner::P1,
+                player_1,
+                player_2,
+                config,
+                score,
+            })
+        );
+    }
+
+    #[test]
+    fn test_process_action_both_players_commit() {
+        let state_pubkey = Pubkey::new_unique();
+        let player_1_pubkey = Pubkey::new_unique();
+        let player_2_pubkey = Pubkey::new_unique();
+        let player_1_commitment = create_commitment(player_1_pubkey, 1, RPS::Rock);
+        let player_2_commitment = create_commitment(player_2_pubkey, 2, RPS::Paper);
+        let config = GameConfig {
+            entry_proof: None,
+            challenge_duration: DEFAULT_EXPIRY_SLOTS,
+            reveal_duration: DEFAULT_EXPIRY_SLOTS,
+            best_of: 1,
+            commit_mode: CommitMode::BothPlayers,
+            move_set: MoveSet::Classic,
+            commitment_version: CommitmentVersion::V1,
+        };
+
+        let state = process_action(
+            PROGRAM_ID,
+            state_pubkey,
+            GameState::Initialized,
+            Actions::CreateGame {
+                player_1_pubkey,
+                commitment: player_1_commitment,
+                config,
+            },
+            0,
+        )
+        .unwrap();
+        assert_eq!(
+            process_action(
+                PROGRAM_ID,
+                state_pubkey,
+                state,
+                Actions::JoinGame {
+                    player_2_pubkey,
+                    choice: RPS::Paper,
+                    secret: None,
+                },
+                0,
+            ),
+            Err(TransitionError::WrongCommitMode)
+        );
+        let both_committed = process_action(
+            PROGRAM_ID,
+            state_pubkey,
+            state,
+            Actions::JoinGameCommitted {
+                player_2_pubkey,
+                commitment: player_2_commitment,
+                secret: None,
+            },
+            0,
+        )
+        .unwrap();
+        assert_eq!(
+            both_committed,
+            GameState::AcceptingReveal {
+                player_1: PlayerState::Committed {
+                    pubkey: player_1_pubkey,
+                    commitment: player_1_commitment,
+                },
+                player_2: PlayerState::Committed {
+                    pubkey: player_2_pubkey,
+                    commitment: player_2_commitment,
+                },
+                config,
+                score: Score::default(),
+                expiry_slot: 600,
+            }
+        );
+
+        let player_1_reveal = Actions::Reveal {
+            player_pubkey: player_1_pubkey,
+            salt: Salt::V1(1),
+            choice: RPS::Rock,
+        };
+        let player_2_reveal = Actions::Reveal {
+            player_pubkey: player_2_pubkey,
+            salt: Salt::V1(2),
+            choice: RPS::Paper,
+        };
+        assert_eq!(
+            process_action(
+                PROGRAM_ID,
+                state_pubkey,
+                both_committed,
+                Actions::Reveal {
+                    player_pubkey: player_2_pubkey,
+                    salt: Salt::V1(2),
+                    choice: RPS::Scissors,
+                },
+                0,
+            ),
+            Err(TransitionError::InvalidCommitment)
+        );
+        let expected = GameState::AcceptingSettle {
+            result: Winner::P2,
+            player_1: PlayerState::Revealed {
+                pubkey: player_1_pubkey,
+                choice: RPS::Rock,
+            },
+            player_2: PlayerState::Revealed {
+                pubkey: player_2_pubkey,
+                choice: RPS::Paper,
+            },
+            config,
+            score: Score {
+                player_1: 0,
+                player_2: 1,
+                ties: 0,
+            },
+        };
+
+        // reveals can land in either order
+        for (first, second) in [
+            (player_1_reveal, player_2_reveal),
+            (player_2_reveal, player_1_reveal),
+        ] {
+            let state = process_action(PROGRAM_ID, state_pubkey, both_committed, first, 0).unwrap();
+            assert!(matches!(
+                state,
+                GameState::AcceptingReveal {
+                    expiry_slot: 600,
+                    ..
+                }
+            ));
+            assert_eq!(
+                process_action(PROGRAM_ID, state_pubkey, state, second, 0),
+                Ok(expected)
+            );
+        }
+
+        // only player 1 revealed so only they can claim the game on expiry
+        let state =
+            process_action(PROGRAM_ID, state_pubkey, both_committed, player_1_reveal, 0).unwrap();
+        assert_eq!(
+            process_action(
+                PROGRAM_ID,
+                state_pubkey,
+                state,
+                Actions::ExpireGame {
+                    player_pubkey: player_2_pubkey
+                },
+                600,
+            ),
+            Err(TransitionError::OnlyPlayer1CanExpireUnrevealed)
+        );
+        assert!(matches!(
+            process_action(
+                PROGRAM_ID,
+                state_pubkey,
+                state,
+                Actions::ExpireGame {
+                    player_pubkey: player_1_pubkey
+                },
+                600,
+            ),
+            Ok(GameState::AcceptingSettle {
+                result: Winner::P1,
+                ..
+            })
+        ));
+
+        // only player 2 revealed so only they can claim the game on expiry
+        let state =
+            process_action(PROGRAM_ID, state_pubkey, both_committed, player_2_reveal, 0).unwrap();
+        assert_eq!(
+            process_action(
+                PROGRAM_ID,
+                state_pubkey,
+                state,
+                Actions::ExpireGame {
+                    player_pubkey: player_1_pubkey
+                },
+                600,
+            ),
+            Err(TransitionError::OnlyPlayer2CanExpireUnrevealed)
+        );
+        assert!(matches!(
+            process_action(
+                PROGRAM_ID,
+                state_pubkey,
+                state,
+                Actions::ExpireGame {
+                    player_pubkey: player_2_pubkey
+                },
+                600,
+            ),
+            Ok(GameState::AcceptingSettle {
+                result: Winner::P2,
+                ..
+            })
+        ));
+
+        // nobody revealed so it's a draw
+        assert_eq!(
+            process_action(
+                PROGRAM_ID,
+                state_pubkey,
+                both_committed,
+                Actions::ExpireGame {
+                    player_pubkey: Pubkey::new_unique()
+                },
+                600,
+            ),
+            Err(TransitionError::NotInGame)
+        );
+        assert!(matches!(
+            process_action(
+                PROGRAM_ID,
+                state_pubkey,
+                both_committed,
+                Actions::ExpireGame {
+                    player_pubkey: player_1_pubkey
+                },
+                600,
+            ),
+            Ok(GameState::AcceptingSettle {
+                result: Winner::TIE,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_match_winner() {
+        let score = Score {
+            player_1: 1,
+            player_2: 1,
+            ties: MAX_TIED_ROUNDS - 1,
+        };
+        assert_eq!(score.match_winner(3), None);
+        assert_eq!(score.record(Winner::P2).match_winner(3), Some(Winner::P2));
+        // one tie too many and the match is a draw
+        assert_eq!(score.record(Winner::TIE).match_winner(3), Some(Winner::TIE));
+
+        let ties = Score {
+            ties: u8::MAX,
+            ..score
+        };
+        assert_eq!(ties.record(Winner::TIE).ties, u8::MAX);
+    }
+
+    #[test]
+    fn test_move_sets() {
+        let moves = [
+            RPS::Rock,
+            RPS::Paper,
+            RPS::Scissors,
+            RPS::Lizard,
+            RPS::Spock,
+        ];
+        for (i, choice) in moves.into_iter().enumerate() {
+            assert_eq!(u8::from(choice), i as u8);
+            assert_eq!(RPS::try_from(i as u8), Ok(choice));
+            assert_eq!(MoveSet::Classic.contains(choice), i < 3);
+            assert!(MoveSet::Rpsls.contains(choice));
+
+            // every move beats exactly two others and ties itself
+            let wins = moves
+                .iter()
+                .filter(|&&other| MoveSet::Rpsls.round_winner(choice, other) == Winner::P1)
+                .count();
+            assert_eq!(wins, 2);
+            assert_eq!(MoveSet::Rpsls.round_winner(choice, choice), Winner::TIE);
+        }
+        assert_eq!(RPS::try_from(5), Err(TransitionError::InvalidMove));
+
+        assert_eq!(
+            MoveSet::Classic.round_winner(RPS::Rock, RPS::Scissors),
+            Winner::P1
+        );
+        assert_eq!(
+            MoveSet::Classic.round_winner(RPS::Scissors, RPS::Rock),
+            Winner::P2
+        );
+        assert_eq!(
+            MoveSet::Rpsls.round_winner(RPS::Spock, RPS::Rock),
+            Winner::P1
+        );
+        assert_eq!(
+            MoveSet::Rpsls.round_winner(RPS::Spock, RPS::Lizard),
+            Winner::P2
+        );
+    }
+
+    #[test]
+    fn test_process_action_rpsls() {
+        let state_pubkey = Pubkey::new_unique();
+        let player_1_pubkey = Pubkey::new_unique();
+        let player_2_pubkey = Pubkey::new_unique();
+        let config = GameConfig {
+            entry_proof: None,
+            challenge_duration: DEFAULT_EXPIRY_SLOTS,
+            reveal_duration: DEFAULT_EXPIRY_SLOTS,
+            best_of: 1,
+            commit_mode: CommitMode::Player1,
+            move_set: MoveSet::Rpsls,
+            commitment_version: CommitmentVersion::V1,
+        };
+        let create = |config, choice| Actions::CreateGame {
+            player_1_pubkey,
+            commitment: create_commitment(player_1_pubkey, 7, choice),
+            config,
+        };
+        let join = Actions::JoinGame {
+            player_2_pubkey,
+            choice: RPS::Spock,
+            secret: None,
+        };
+        let reveal = |choice| Actions::Reveal {
+            player_pubkey: player_1_pubkey,
+            salt: Salt::V1(7),
+            choice,
+        };
+
+        let state = process_action(
+            PROGRAM_ID,
+            state_pubkey,
+            GameState::Initialized,
+            create(config, RPS::Lizard),
+            0,
+        )
+        .unwrap();
+        let state = process_action(PROGRAM_ID, state_pubkey, state, join, 0).unwrap();
+        assert!(matches!(
+            process_action(PROGRAM_ID, state_pubkey, state, reveal(RPS::Lizard), 0),
+            Ok(GameState::AcceptingSettle {
+                result: Winner::P1,
+                ..
+            })
+        ));
+
+        // spock and lizard aren't playable in a classic game
+        let classic = GameConfig {
+            move_set: MoveSet::Classic,
+            ..config
+        };
+        let state = process_action(
+            PROGRAM_ID,
+            state_pubkey,
+            GameState::Initialized,
+            create(classic, RPS::Lizard),
+            0,
+        )
+        .unwrap();
+        assert_eq!(
+            process_action(PROGRAM_ID, state_pubkey, state, join, 0),
+            Err(TransitionError::InvalidMove)
+        );
+        let state = process_action(
+            PROGRAM_ID,
+            state_pubkey,
+            state,
+            Actions::JoinGame {
+                player_2_pubkey,
+                choice: RPS::Paper,
+                secret: None,
+            },
+            0,
+        )
+        .unwrap();
+        assert_eq!(
+            process_action(PROGRAM_ID, state_pubkey, state, reveal(RPS::Lizard), 0),
+            Err(TransitionError::InvalidMove)
+        );
+    }
+
+    #[test]
+    fn test_process_action_cancel() {
+        let state_pubkey = Pubkey::new_unique();
+        let player_1_pubkey = Pubkey::new_unique();
+        let player_2_pubkey = Pubkey::new_unique();
+        let player_1 = PlayerState::Committed {
+            pubkey: player_1_pubkey,
+            commitment: create_commitment(player_1_pubkey, 36, RPS::Rock),
+        };
+        let config = GameConfig {
+            entry_proof: None,
+            challenge_duration: DEFAULT_EXPIRY_SLOTS,
+            reveal_duration: DEFAULT_EXPIRY_SLOTS,
+            best_of: 1,
+            commit_mode: CommitMode::Player1,
+            move_set: MoveSet::Classic,
+            commitment_version: CommitmentVersion::V1,
+        };
+        let state = GameState::AcceptingChallenge {
+            config,
+            player_1,
+            expiry_slot: 600,
+        };
+
+        assert_eq!(
+            process_action(
+                PROGRAM_ID,
+                state_pubkey,
+                state,
+                Actions::CancelGame {
+                    player_pubkey: player_2_pubkey
+                },
+                0,
+            ),
+            Err(TransitionError::NotPlayer1)
+        );
+        // no need to wait for the challenge to expire
+        assert_eq!(
+            process_action(
+                PROGRAM_ID,
+                state_pubkey,
+                state,
+                Actions::CancelGame {
+                    player_pubkey: player_1_pubkey
+                },
+                0,
+            ),
+            Ok(GameState::Cancelled { player_1, config })
+        );
+
+        // too late once player 2 joined
+        let state = process_action(
+            PROGRAM_ID,
+            state_pubkey,
+            state,
+            Actions::JoinGame {
+                player_2_pubkey,
+                choice: RPS::Paper,
+                secret: None,
+            },
+            0,
+        )
+        .unwrap();
+        assert_eq!(
+            process_action(
+                PROGRAM_ID,
+                state_pubkey,
+                state,
+                Actions::CancelGame {
+                    player_pubkey: player_1_pubkey
+                },
+                0,
+            ),
+            Err(TransitionError::InvalidTransition)
+        );
+    }
+
+    #[test]
+    fn test_process_action_commitment_v2() {
+        let state_pubkey = Pubkey::new_unique();
+        let player_1_pubkey = Pubkey::new_unique();
+        let player_2_pubkey = Pubkey::new_unique();
+        let config = GameConfig {
+            entry_proof: None,
+            challenge_duration: DEFAULT_EXPIRY_SLOTS,
+            reveal_duration: DEFAULT_EXPIRY_SLOTS,
+            best_of: 1,
+            commit_mode: CommitMode::Player1,
+            move_set: MoveSet::Classic,
+            commitment_version: CommitmentVersion::V2,
+        };
+        let salt = [9; 32];
+        let commitment =
+            create_commitment_v2(PROGRAM_ID, state_pubkey, player_1_pubkey, salt, RPS::Paper);
+        let state = process_action(
+            PROGRAM_ID,
+            state_pubkey,
+            GameState::Initialized,
+            Actions::CreateGame {
+                player_1_pubkey,
+                commitment,
+                config,
+            },
+            0,
+        )
+        .unwrap();
+        let state = process_action(
+            PROGRAM_ID,
+            state_pubkey,
+            state,
+            Actions::JoinGame {
+                player_2_pubkey,
+                choice: RPS::Rock,
+                secret: None,
+            },
+            0,
+        )
+        .unwrap();
+        let reveal = |salt| Actions::Reveal {
+            player_pubkey: player_1_pubkey,
+            salt,
+            choice: RPS::Paper,
+        };
+
+        // a v1 salt doesn't open a v2 commitment
+        assert_eq!(
+            process_action(PROGRAM_ID, state_pubkey, state, reveal(Salt::V1(9)), 0),
+            Err(TransitionError::InvalidCommitment)
+        );
+        // only valid for the program and game it was made for
+        assert_eq!(
+            process_action(
+                Pubkey::new_unique(),
+                state_pubkey,
+                state,
+                reveal(Salt::V2(salt)),
+                0
+            ),
+            Err(TransitionError::InvalidCommitment)
+        );
+        assert_eq!(
+            process_action(
+                PROGRAM_ID,
+                Pubkey::new_unique(),
+                state,
+                reveal(Salt::V2(salt)),
+                0
+            ),
+            Err(TransitionError::InvalidCommitment)
+        );
+        assert!(matches!(
+            process_action(PROGRAM_ID, state_pubkey, state, reveal(Salt::V2(salt)), 0),
+            Ok(GameState::AcceptingSettle {
+                result: Winner::P1,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_process_action_commitment_replay() {
+        let game_a = Pubkey::new_unique();
+        let game_b = Pubkey::new_unique();
+        let player_1_pubkey = Pubkey::new_unique();
+        let player_2_pubkey = Pubkey::new_unique();
+        let config = GameConfig {
+            entry_proof: None,
+            challenge_duration: DEFAULT_EXPIRY_SLOTS,
+            reveal_duration: DEFAULT_EXPIRY_SLOTS,
+            best_of: 1,
+            commit_mode: CommitMode::BothPlayers,
+            move_set: MoveSet::Classic,
+            commitment_version: CommitmentVersion::V2,
+        };
+        let salt = [4; 32];
+        // made for game a
+        let commitment =
+            create_commitment_v2(PROGRAM_ID, game_a, player_1_pubkey, salt, RPS::Scissors);
+        let both_committed = |game, player_1_commitment, player_2_commitment| {
+            let state = process_action(
+                PROGRAM_ID,
+                game,
+                GameState::Initialized,
+                Actions::CreateGame {
+                    player_1_pubkey,
+                    commitment: player_1_commitment,
+                    config,
+                },
+                0,
+            )
+            .unwrap();
+            process_action(
+                PROGRAM_ID,
+                game,
+                state,
+                Actions::JoinGameCommitted {
+                    player_2_pubkey,
+                    commitment: player_2_commitment,
+                    secret: None,
+                },
+                0,
+            )
+            .unwrap()
+        };
+        let reveal = |player_pubkey| Actions::Reveal {
+            player_pubkey,
+            salt: Salt::V2(salt),
+            choice: RPS::Scissors,
+        };
+
+        let state = both_committed(game_a, commitment, [0; 32]);
+        assert!(process_action(PROGRAM_ID, game_a, state, reveal(player_1_pubkey), 0).is_ok());
+
+        // the same commitment copied into another game can't be revealed there
+        let state = both_committed(game_b, commitment, [0; 32]);
+        assert_eq!(
+            process_action(PROGRAM_ID, game_b, state, reveal(player_1_pubkey), 0),
+            Err(TransitionError::InvalidCommitment)
+        );
+
+        // player 2 copying player 1's commitment can't open it with the salt
+        // player 1 revealed
+        let state = both_committed(game_a, commitment, commitment);
+        let state = process_action(PROGRAM_ID, game_a, state, reveal(player_1_pubkey), 0).unwrap();
+        assert_eq!(
+            process_action(PROGRAM_ID, game_a, state, reveal(player_2_pubkey), 0),
+            Err(TransitionError::InvalidCommitment)
+        );
+
+        // v1 commitments don't know their game, the reason new v1 games have
+        // to be allowed by the program
+        let v1 = GameConfig {
+            commitment_version: CommitmentVersion::V1,
+            commit_mode: CommitMode::Player1,
+            ..config
+        };
+        let commitment = create_commitment(player_1_pubkey, 4, RPS::Scissors);
+        for game in [game_a, game_b] {
+            let state = process_action(
+                PROGRAM_ID,
+                game,
+                GameState::Initialized,
+                Actions::CreateGame {
+                    player_1_pubkey,
+                    commitment,
+                    config: v1,
+                },
+                0,
+            )
+            .unwrap();
+            let state = process_action(
+                PROGRAM_ID,
+                game,
+                state,
+                Actions::JoinGame {
+                    player_2_pubkey,
+                    choice: RPS::Rock,
+                    secret: None,
+                },
+                0,
+            )
+            .unwrap();
+            let reveal = Actions::Reveal {
+                player_pubkey: player_1_pubkey,
+                salt: Salt::V1(4),
+                choice: RPS::Scissors,
+            };
+            assert!(process_action(PROGRAM_ID, game, state, reveal, 0).is_ok());
+        }
+    }
+
+    pub fn create_commitment_v2(
+        program_id: Pubkey,
+        game: Pubkey,
+        pubkey: Pubkey,
+        salt: [u8; 32],
+        choice: RPS,
+    ) -> [u8; 32] {
+        hashv(&[
+            COMMITMENT_DOMAIN,
+            program_id.as_ref(),
+            game.as_ref(),
+            pubkey.as_ref(),
+            &salt,
+            &[choice.into()],
+        ])
+        .0
+    }
+
+    pub fn create_commitment(pubkey: Pubkey, salt: u64, choice: RPS) -> [u8; 32] {
+        let hash = hashv(&[
+            pubkey.as_ref(),
+            &salt.to_le_bytes(),
+            [choice.into()].as_ref(),
+        ]);
+        // let mut buf = [0; 32];
+        // buf.copy_from_slice(hash.as_ref());
+        // buf
+
+        // idk why but this works
+        hash.0
+    }
+
+    pub fn create_entry_proof(pubkey: Pubkey, secret: u64) -> [u8; 32] {
+        let hash = hashv(&[pubkey.as_ref(), &secret.to_le_bytes()]);
+        // let mut buf = [0; 32];
+        // buf.copy_from_slice(hash.as_ref());
+        // buf
+
+        // idk why but this works
+        hash.0
+    }
+}
@@ -0,0 +1,340 @@
+use anchor_lang::prelude::*;
+
+use crate::logic::{GameState, Score, Winner, RPS};
+
+/// Game data shared by both programs, each program's `Game` account wraps it
+/// and derefs to it.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, AnchorSerialize, AnchorDeserialize)]
+pub struct GameData {
+    pub seed: u64,
+    pub wager_amount: u64,
+    pub fee_amount: u64,
+    pub state: GameState,
+}
+
+/// What the escrow owes out once a game settles.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct Payouts {
+    pub player_1: u64,
+    pub player_2: u64,
+    pub treasury: u64,
+}
+
+impl GameData {
+    pub fn player_1(self) -> Option<Pubkey> {
+        match self.state {
+            GameState::AcceptingChallenge { player_1, .. } => Some(player_1.pubkey()),
+            GameState::AcceptingReveal { player_1, .. } => Some(player_1.pubkey()),
+            GameState::AcceptingCommit { player_1, .. } => Some(player_1.pubkey()),
+            GameState::AcceptingChoice { player_1, .. } => Some(player_1.pubkey()),
+            GameState::AcceptingSettle { player_1, .. } => Some(player_1.pubkey()),
+            GameState::Settled { player_1, .. } => Some(player_1.pubkey()),
+            GameState::Cancelled { player_1, .. } => Some(player_1.pubkey()),
+            _ => None,
+        }
+    }
+    pub fn player_2(self) -> Option<Pubkey> {
+        match self.state {
+            GameState::AcceptingReveal { player_2, .. } => Some(player_2.pubkey()),
+            GameState::AcceptingCommit { player_2, .. } => Some(player_2.pubkey()),
+            GameState::AcceptingChoice { player_2, .. } => Some(player_2.pubkey()),
+            GameState::AcceptingSettle { player_2, .. } => Some(player_2.pubkey()),
+            GameState::Settled { player_2, .. } => Some(player_2.pubkey()),
+            _ => None,
+        }
+    }
+    pub fn choices(self) -> (Option<RPS>, Option<RPS>) {
+        match self.state {
+            GameState::AcceptingChallenge { player_1, .. }
+            | GameState::Cancelled { player_1, .. } => (player_1.choice_or_unrevealed(), None),
+            GameState::AcceptingReveal {
+                player_1, player_2, ..
+            }
+            | GameState::AcceptingCommit {
+                player_1, player_2, ..
+            }
+            | GameState::AcceptingChoice {
+                player_1, player_2, ..
+            }
+            | GameState::AcceptingSettle {
+                player_1, player_2, ..
+            }
+            | GameState::Settled {
+                player_1, player_2, ..
+            } => (
+                player_1.choice_or_unrevealed(),
+                player_2.choice_or_unrevealed(),
+            ),
+            GameState::Initialized => (None, None),
+        }
+    }
+    pub fn score(self) -> Score {
+        match self.state {
+            GameState::AcceptingReveal { score, .. }
+            | GameState::AcceptingCommit { score, .. }
+            | GameState::AcceptingChoice { score, .. }
+            | GameState::AcceptingSettle { score, .. }
+            | GameState::Settled { score, .. } => score,
+            _ => Score::default(),
+        }
+    }
+    pub fn result(self) -> Option<Winner> {
+        match self.state {
+            GameState::AcceptingSettle { result, .. } | GameState::Settled { result, .. } => {
+                Some(result)
+            }
+            _ => None,
+        }
+    }
+
+    // nobody joined before player 1 expired it, they play against themselves
+    pub fn is_p1_expired(self) -> bool {
+        self.player_2().is_some() && self.player_1() == self.player_2()
+    }
+
+    /// Splits the escrow of a settled game. Player 2 gets whatever part of the
+    /// fee the treasury doesn't take, an unmatched game just goes back to
+    /// player 1 with the fee.
+    pub fn payouts(self, treasury_fee: u64) -> Option<Payouts> {
+        let result = match self.state {
+            GameState::Settled { result, .. } => result,
+            _ => return None,
+        };
+        if self.is_p1_expired() {
+            return Some(Payouts {
+                player_1: self.wager_amount.checked_add(self.fee_amount)?,
+                player_2: 0,
+                treasury: 0,
+            });
+        }
+
+        let pot = self.wager_amount.checked_mul(2)?;
+        let (player_1, player_2) = match result {
+            Winner::P1 => (pot, 0),
+            Winner::P2 => (0, pot),
+            Winner::TIE => (self.wager_amount, self.wager_amount),
+        };
+        Some(Payouts {
+            player_1,
+            player_2: player_2.checked_add(self.fee_amount.checked_sub(treasury_fee)?)?,
+            treasury: treasury_fee,
+        })
+    }
+
+    /// Moves both players' wagers out of their open games and records the
+    /// result, an unmatched game only counts for player 1's open games.
+    pub fn record_settlement(
+        self,
+        player_1: &mut PlayerStats,
+        player_2: &mut PlayerStats,
+    ) -> Option<()> {
+        let result = self.result()?;
+        player_1.leave_game(self.wager_amount)?;
+        if self.is_p1_expired() {
+            return Some(());
+        }
+        player_2.leave_game(self.wager_amount)?;
+
+        match result {
+            Winner::P1 => {
+                player_1.record_win(self.wager_amount)?;
+                player_2.record_loss(self.wager_amount)
+            }
+            Winner::P2 => {
+                player_1.record_loss(self.wager_amount)?;
+                player_2.record_win(self.wager_amount)
+            }
+            Winner::TIE => {
+                player_1.record_draw()?;
+                player_2.record_draw()
+            }
+        }
+    }
+}
+
+/// Player stats shared by both programs, each program's `PlayerInfo` account
+/// wraps it and derefs to it.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, AnchorSerialize, AnchorDeserialize)]
+pub struct PlayerStats {
+    pub games_won: u64,
+    pub games_drawn: u64,
+    pub games_lost: u64,
+
+    pub lifetime_wagering: u64,
+    pub lifetime_earnings: i64,
+
+    pub amount_in_games: u64,
+}
+
+impl PlayerStats {
+    pub fn enter_game(&mut self, wager_amount: u64) -> Option<()> {
+        self.amount_in_games = self.amount_in_games.checked_add(wager_amount)?;
+        self.lifetime_wagering = self.lifetime_wagering.checked_add(wager_amount)?;
+        Some(())
+    }
+    pub fn leave_game(&mut self, wager_amount: u64) -> Option<()> {
+        self.amount_in_games = self.amount_in_games.checked_sub(wager_amount)?;
+        Some(())
+    }
+    // the game never happened so it doesn't count towards wagering either
+    pub fn cancel_game(&mut self, wager_amount: u64) -> Option<()> {
+        self.leave_game(wager_amount)?;
+        self.lifetime_wagering = self.lifetime_wagering.checked_sub(wager_amount)?;
+        Some(())
+    }
+    pub fn record_win(&mut self, wager_amount: u64) -> Option<()> {
+        self.games_won = self.games_won.checked_add(1)?;
+        self.lifetime_earnings = self
+            .lifetime_earnings
+            .checked_add(i64::try_from(wager_amount).ok()?)?;
+        Some(())
+    }
+    pub fn record_loss(&mut self, wager_amount: u64) -> Option<()> {
+        self.games_lost = self.games_lost.checked_add(1)?;
+        self.lifetime_earnings = self
+            .lifetime_earnings
+            .checked_sub(i64::try_from(wager_amount).ok()?)?;
+        Some(())
+    }
+    pub fn record_draw(&mut self) -> Option<()> {
+        self.games_drawn = self.games_drawn.checked_add(1)?;
+        Some(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::logic::{CommitMode, CommitmentVersion, GameConfig, MoveSet, PlayerState};
+
+    fn settled(result: Winner, player_1: Pubkey, player_2: Pubkey) -> GameData {
+        let config = GameConfig {
+            entry_proof: None,
+            challenge_duration: 600,
+            reveal_duration: 600,
+            best_of: 1,
+            commit_mode: CommitMode::Player1,
+            move_set: MoveSet::Classic,
+            commitment_version: CommitmentVersion::V1,
+        };
+        GameData {
+            seed: 1,
+            wager_amount: 1000,
+            fee_amount: 100,
+            state: GameState::Settled {
+                result,
+                player_1: PlayerState::Revealed {
+                    pubkey: player_1,
+                    choice: RPS::Rock,
+                },
+                player_2: PlayerState::Revealed {
+                    pubkey: player_2,
+                    choice: RPS::Paper,
+                },
+                config,
+                score: Score::default(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_payouts() {
+        let player_1 = Pubkey::new_unique();
+        let player_2 = Pubkey::new_unique();
+
+        let game = settled(Winner::P1, player_1, player_2);
+        assert_eq!(
+            game.payouts(10),
+            Some(Payouts {
+                player_1: 2000,
+                player_2: 90,
+                treasury: 10,
+            })
+        );
+
+        let game = settled(Winner::P2, player_1, player_2);
+        assert_eq!(
+            game.payouts(0),
+            Some(Payouts {
+                player_1: 0,
+                player_2: 2100,
+                treasury: 0,
+            })
+        );
+
+        let game = settled(Winner::TIE, player_1, player_2);
+        assert_eq!(
+            game.payouts(100),
+            Some(Payouts {
+                player_1: 1000,
+                player_2: 1000,
+                treasury: 100,
+            })
+        );
+
+        // treasury can't take more than the fee
+        assert_eq!(game.payouts(101), None);
+
+        // unmatched game refunds player 1 and the treasury gets nothing
+        let game = settled(Winner::P1, player_1, player_1);
+        assert_eq!(
+            game.payouts(10),
+            Some(Payouts {
+                player_1: 1100,
+                player_2: 0,
+                treasury: 0,
+            })
+        );
+
+        let mut game = settled(Winner::P1, player_1, player_2);
+        game.state = GameState::Initialized;
+        assert_eq!(game.payouts(10), None);
+    }
+
+    #[test]
+    fn test_record_settlement() {
+        let player_1 = Pubkey::new_unique();
+        let player_2 = Pubkey::new_unique();
+        let mut stats_1 = PlayerStats::default();
+        let mut stats_2 = PlayerStats::default();
+        stats_1.enter_game(1000).unwrap();
+        stats_2.enter_game(1000).unwrap();
+
+        settled(Winner::P2, player_1, player_2)
+            .record_settlement(&mut stats_1, &mut stats_2)
+            .unwrap();
+        assert_eq!(
+            stats_1,
+            PlayerStats {
+                games_lost: 1,
+                lifetime_wagering: 1000,
+                lifetime_earnings: -1000,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            stats_2,
+            PlayerStats {
+                games_won: 1,
+                lifetime_wagering: 1000,
+                lifetime_earnings: 1000,
+                ..Default::default()
+            }
+        );
+
+        // wager was never moved in for player 2 so nothing to move out
+        let mut stats_1 = PlayerStats::default();
+        let mut untouched = PlayerStats::default();
+        stats_1.enter_game(1000).unwrap();
+        settled(Winner::P1, player_1, player_1)
+            .record_settlement(&mut stats_1, &mut untouched)
+            .unwrap();
+        assert_eq!(stats_1.amount_in_games, 0);
+        assert_eq!(stats_1.games_won, 0);
+        assert_eq!(untouched, PlayerStats::default());
+
+        // leaving a game that was never entered underflows
+        let mut empty = PlayerStats::default();
+        assert_eq!(empty.cancel_game(1), None);
+    }
+}
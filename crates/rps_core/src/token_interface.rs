@@ -0,0 +1,352 @@
+//! Just enough of a token interface to take both the legacy token program and
+//! Token-2022, anchor 0.26 only knows about the legacy one. Both programs share
+//! the base mint and account layouts so Token-2022's unpacking reads either.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::{invoke, invoke_signed};
+use anchor_lang::solana_program::system_instruction;
+use spl_token_2022::extension::transfer_fee::{TransferFeeAmount, TransferFeeConfig};
+use spl_token_2022::extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions};
+use spl_token_2022::state::{Account as TokenAccountState, Mint as MintState};
+
+pub fn is_token_program(key: &Pubkey) -> bool {
+    *key == spl_token::ID || *key == spl_token_2022::ID
+}
+
+// wrapped sol of either token program
+pub fn is_native_mint(key: &Pubkey) -> bool {
+    *key == spl_token::native_mint::ID || *key == spl_token_2022::native_mint::ID
+}
+
+pub fn is_wallet(info: &AccountInfo) -> bool {
+    *info.owner == anchor_lang::system_program::ID
+}
+
+/// Why a mint can't be wagered.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum MintError {
+    InvalidMint,
+    // tokens could never leave escrow
+    NonTransferable,
+    // the delegate could pull tokens out of escrow
+    PermanentDelegate,
+    // escrow accounts could start out frozen
+    DefaultAccountState,
+}
+
+/// The parts of a mint the escrow cares about.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct MintInfo {
+    pub decimals: u8,
+    pub transfer_fee: Option<TransferFeeConfig>,
+    // the issuer could freeze escrow mid game, up to the admin whether
+    // that's acceptable
+    pub freeze_authority: bool,
+    // space an escrow token account for this mint needs
+    pub account_len: usize,
+}
+
+impl MintInfo {
+    /// What has to be sent for `amount` to land after the mint's transfer fee.
+    pub fn pre_fee_amount(&self, amount: u64, epoch: u64) -> Option<u64> {
+        match self.transfer_fee {
+            Some(transfer_fee) => transfer_fee
+                .get_epoch_fee(epoch)
+                .calculate_pre_fee_amount(amount),
+            None => Some(amount),
+        }
+    }
+}
+
+pub fn mint_info(mint: &AccountInfo) -> std::result::Result<MintInfo, MintError> {
+    if !is_token_program(mint.owner) {
+        return Err(MintError::InvalidMint);
+    }
+    let data = mint.try_borrow_data().map_err(|_| MintError::InvalidMint)?;
+    let state =
+        StateWithExtensions::<MintState>::unpack(&data).map_err(|_| MintError::InvalidMint)?;
+    let extensions = state
+        .get_extension_types()
+        .map_err(|_| MintError::InvalidMint)?;
+
+    for extension in extensions.iter() {
+        match extension {
+            ExtensionType::NonTransferable => return Err(MintError::NonTransferable),
+            ExtensionType::PermanentDelegate => return Err(MintError::PermanentDelegate),
+            ExtensionType::DefaultAccountState => return Err(MintError::DefaultAccountState),
+            _ => {}
+        }
+    }
+
+    Ok(MintInfo {
+        decimals: state.base.decimals,
+        freeze_authority: state.base.freeze_authority.is_some(),
+        transfer_fee: state.get_extension::<TransferFeeConfig>().ok().copied(),
+        account_len: ExtensionType::get_account_len::<TokenAccountState>(
+            &ExtensionType::get_required_init_account_extensions(&extensions),
+        ),
+    })
+}
+
+/// Base state of a token account from either token program.
+pub fn token_account(info: &AccountInfo) -> Option<TokenAccountState> {
+    if !is_token_program(info.owner) {
+        return None;
+    }
+    let data = info.try_borrow_data().ok()?;
+    let state = StateWithExtensions::<TokenAccountState>::unpack(&data).ok()?;
+    Some(state.base)
+}
+
+pub fn is_token_account(info: &AccountInfo, owner: &Pubkey, mint: &Pubkey) -> bool {
+    token_account(info).is_some_and(|account| account.owner == *owner && account.mint == *mint)
+}
+
+/// Native mint games can be paid into and out of with `owner`'s wallet
+/// directly instead of a wrapped sol token account.
+pub fn is_native_wallet(info: &AccountInfo, owner: &Pubkey, mint: &Pubkey) -> bool {
+    is_native_mint(mint) && info.key == owner && is_wallet(info)
+}
+
+// transfer fees withheld in an account have to be harvested before it closes
+pub fn withheld_fees(info: &AccountInfo) -> Option<u64> {
+    let data = info.try_borrow_data().ok()?;
+    let state = StateWithExtensions::<TokenAccountState>::unpack(&data).ok()?;
+    Some(
+        state
+            .get_extension::<TransferFeeAmount>()
+            .map_or(0, |fees| u64::from(fees.withheld_amount)),
+    )
+}
+
+/// Creates a token account at a pda owned by this program and hands it to
+/// `owner`, anchor's `init` can only do this for the legacy token program.
+#[allow(clippy::too_many_arguments)]
+pub fn create_token_account<'info>(
+    payer: AccountInfo<'info>,
+    account: AccountInfo<'info>,
+    mint: AccountInfo<'info>,
+    owner: &Pubkey,
+    token_program: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+    account_len: usize,
+    account_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let rent = Rent::get()?.minimum_balance(account_len);
+    let lamports = account.lamports();
+    if lamports == 0 {
+        invoke_signed(
+            &system_instruction::create_account(
+                payer.key,
+                account.key,
+                rent,
+                account_len as u64,
+                token_program.key,
+            ),
+            &[payer, account.clone(), system_program],
+            account_seeds,
+        )?;
+    } else {
+        // create_account fails if anyone sent lamports to the address first,
+        // so top it up and allocate and assign it separately like anchor's
+        // init does
+        let top_up = rent.saturating_sub(lamports);
+        if top_up > 0 {
+            invoke(
+                &system_instruction::transfer(payer.key, account.key, top_up),
+                &[payer, account.clone(), system_program.clone()],
+            )?;
+        }
+        invoke_signed(
+            &system_instruction::allocate(account.key, account_len as u64),
+            &[account.clone(), system_program.clone()],
+            account_seeds,
+        )?;
+        invoke_signed(
+            &system_instruction::assign(account.key, token_program.key),
+            &[account.clone(), system_program],
+            account_seeds,
+        )?;
+    }
+    invoke(
+        &spl_token_2022::instruction::initialize_account3(
+            token_program.key,
+            account.key,
+            mint.key,
+            owner,
+        )?,
+        &[account, mint, token_program],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use anchor_lang::solana_program::program_pack::Pack;
+    use spl_token_2022::extension::non_transferable::NonTransferable;
+    use spl_token_2022::extension::StateWithExtensionsMut;
+
+    fn legacy_mint() -> Vec<u8> {
+        let mut data = vec![0; MintState::LEN];
+        MintState::pack(
+            MintState {
+                decimals: 6,
+                is_initialized: true,
+                ..Default::default()
+            },
+            &mut data,
+        )
+        .unwrap();
+        data
+    }
+
+    // the extensions the tests build mints with
+    #[derive(Clone, Copy)]
+    enum Extension {
+        TransferFee,
+        NonTransferable,
+    }
+
+    fn mint_with(extension: Extension) -> Vec<u8> {
+        let extension_type = match extension {
+            Extension::TransferFee => ExtensionType::TransferFeeConfig,
+            Extension::NonTransferable => ExtensionType::NonTransferable,
+        };
+        let mut data = vec![0; ExtensionType::get_account_len::<MintState>(&[extension_type])];
+        let mut state =
+            StateWithExtensionsMut::<MintState>::unpack_uninitialized(&mut data).unwrap();
+        match extension {
+            Extension::TransferFee => {
+                let config = state.init_extension::<TransferFeeConfig>(true).unwrap();
+                config.newer_transfer_fee.transfer_fee_basis_points = 100.into();
+                config.newer_transfer_fee.maximum_fee = 50.into();
+                config.older_transfer_fee = config.newer_transfer_fee;
+            }
+            Extension::NonTransferable => {
+                state.init_extension::<NonTransferable>(true).unwrap();
+            }
+        }
+        state.base = MintState {
+            decimals: 9,
+            is_initialized: true,
+            ..Default::default()
+        };
+        state.pack_base();
+        state.init_account_type().unwrap();
+        data
+    }
+
+    fn with_mint<T>(owner: Pubkey, data: &mut [u8], f: impl FnOnce(&AccountInfo) -> T) -> T {
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let info = AccountInfo::new(&key, false, false, &mut lamports, data, &owner, false, 0);
+        f(&info)
+    }
+
+    #[test]
+    fn test_mint_info() {
+        let info = with_mint(spl_token::ID, &mut legacy_mint(), mint_info).unwrap();
+        assert_eq!(info.decimals, 6);
+        assert_eq!(info.transfer_fee, None);
+        assert_eq!(info.account_len, TokenAccountState::LEN);
+        assert_eq!(info.pre_fee_amount(1000, 0), Some(1000));
+
+        // not owned by a token program
+        assert_eq!(
+            with_mint(Pubkey::new_unique(), &mut legacy_mint(), mint_info),
+            Err(MintError::InvalidMint)
+        );
+    }
+
+    #[test]
+    fn test_freezable_mint() {
+        let mut data = vec![0; MintState::LEN];
+        MintState::pack(
+            MintState {
+                decimals: 6,
+                is_initialized: true,
+                freeze_authority: Some(Pubkey::new_unique()).into(),
+                ..Default::default()
+            },
+            &mut data,
+        )
+        .unwrap();
+        let info = with_mint(spl_token::ID, &mut data, mint_info).unwrap();
+        assert!(info.freeze_authority);
+        assert!(
+            !with_mint(spl_token::ID, &mut legacy_mint(), mint_info)
+                .unwrap()
+                .freeze_authority
+        );
+    }
+
+    #[test]
+    fn test_transfer_fee_mint() {
+        let info = with_mint(
+            spl_token_2022::ID,
+            &mut mint_with(Extension::TransferFee),
+            mint_info,
+        )
+        .unwrap();
+        assert_eq!(info.decimals, 9);
+        assert!(info.account_len > TokenAccountState::LEN);
+
+        // 1% fee so 1000 landing needs 1011 sent, 1011 * 1% rounds up to 11
+        assert_eq!(info.pre_fee_amount(1000, 0), Some(1011));
+        let fee = info.transfer_fee.unwrap();
+        assert_eq!(fee.calculate_epoch_fee(0, 1011), Some(11));
+
+        // capped at the maximum fee
+        assert_eq!(info.pre_fee_amount(100_000, 0), Some(100_050));
+    }
+
+    #[test]
+    fn test_rejected_extensions() {
+        assert_eq!(
+            with_mint(
+                spl_token_2022::ID,
+                &mut mint_with(Extension::NonTransferable),
+                mint_info,
+            ),
+            Err(MintError::NonTransferable)
+        );
+    }
+
+    #[test]
+    fn test_native_wallet() {
+        let wallet = Pubkey::new_unique();
+        let system = anchor_lang::system_program::ID;
+        let mut lamports = 0;
+        let mut data = [];
+        let info = AccountInfo::new(
+            &wallet,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            &system,
+            false,
+            0,
+        );
+
+        assert!(is_native_wallet(
+            &info,
+            &wallet,
+            &spl_token::native_mint::ID
+        ));
+        assert!(is_native_wallet(
+            &info,
+            &wallet,
+            &spl_token_2022::native_mint::ID
+        ));
+        // only wrapped sol can be paid with a wallet
+        assert!(!is_native_wallet(&info, &wallet, &Pubkey::new_unique()));
+        // has to be the owner's own wallet
+        assert!(!is_native_wallet(
+            &info,
+            &Pubkey::new_unique(),
+            &spl_token::native_mint::ID
+        ));
+    }
+}
@@ -1,27 +1,58 @@
 #![allow(clippy::result_large_err)]
 use anchor_lang::prelude::*;
+use anchor_spl::token::spl_token::native_mint;
 use anchor_spl::token::*;
 
 use rps::cpi::accounts::{CreatePlayerInfo, JoinGame};
 use rps::cpi::{create_player_info, join_game};
-use rps::logic::RPS;
+use rps::logic::{GameState, RPS};
 use rps::program::Rps;
-use rps::{self, Game, PlayerInfo};
+use rps::{self, Config, Game, PlayerInfo};
+use rps_core::escrow::{Escrow, TokenEscrow};
+use rps_core::state::{GameData, PlayerStats};
+use rps_core::token_interface;
+use rps_token::program::RpsToken;
+use rps_token::{Config as TokenConfig, Game as TokenGame, PlayerInfo as TokenPlayerInfo};
+
+pub mod nav;
+pub mod risk;
+
+use nav::Nav;
+use risk::RiskParams;
 
 declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
 
 const POOL_SEED: &[u8; 4] = b"pool";
 const AUTHORITY_SEED: &[u8; 9] = b"authority";
 const MINT_SEED: &[u8; 4] = b"mint";
+const WITHDRAWAL_SEED: &[u8; 10] = b"withdrawal";
+const DEPOSIT_SEED: &[u8; 7] = b"deposit";
+const EPOCH_SEED: &[u8; 5] = b"epoch";
+const TOKENS_SEED: &[u8; 6] = b"tokens";
+
+// about a month of slots
+pub const MAX_EPOCH_DURATION: u64 = 2 * 60 * 60 * 24 * 30;
 
 #[program]
 pub mod blp {
     use super::*;
 
-    pub fn create_pool(ctx: Context<CreatePool>, seed: u64) -> Result<()> {
+    pub fn create_pool(
+        ctx: Context<CreatePool>,
+        seed: u64,
+        epoch_duration: u64,
+        risk: RiskParams,
+        admin: Pubkey,
+    ) -> Result<()> {
+        ctx.accounts.pool.set_risk(risk)?;
         ctx.accounts.pool.seed = seed;
+        ctx.accounts.pool.set_epoch_duration(epoch_duration)?;
+        ctx.accounts.pool.start_epoch(Clock::get()?.slot)?;
         ctx.accounts.pool.authority = ctx.accounts.pool_authority.key();
-        ctx.accounts.pool.bot_authority = ctx.accounts.bot_authority.key();
+        ctx.accounts.pool.authority_player_info = ctx.accounts.pool_authority_player_info.key();
+        ctx.accounts
+            .pool
+            .set_roles(admin, ctx.accounts.bot_authority.key())?;
         ctx.accounts.pool.lp_token_mint = ctx.accounts.lp_token_mint.key();
 
         // creating lp token mint
@@ -37,60 +68,100 @@ pub mod blp {
             Some(&ctx.accounts.pool_authority.key()),
         )?;
 
-        // registering the pool to be able to play
-        create_player_info(CpiContext::new(
+        // registering the pool to be able to play, the pool authority pays
+        // for it so the creator covers the rent first. the creator also
+        // leaves the pool authority rent exempt for good, so paying out can
+        // never leave it with less, see pool_nav
+        let rent = Rent::get()?;
+        let rent = rent.minimum_balance(PlayerInfo::space()) + rent.minimum_balance(0);
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.bot_authority.to_account_info(),
+                    to: ctx.accounts.pool_authority.to_account_info(),
+                },
+            ),
+            rent,
+        )?;
+        create_player_info(CpiContext::new_with_signer(
             ctx.accounts.rps_program.to_account_info(),
             CreatePlayerInfo {
                 owner: ctx.accounts.pool_authority.to_account_info(),
                 player_info: ctx.accounts.pool_authority_player_info.to_account_info(),
                 system_program: ctx.accounts.system_program.to_account_info(),
             },
+            &[&[
+                AUTHORITY_SEED.as_ref(),
+                ctx.accounts.pool.key().as_ref(),
+                &[*ctx.bumps.get("pool_authority").unwrap()],
+            ]],
         ))?;
 
         Ok(())
     }
 
     // to change stuff for the bot
-    // pub fn update_pool(ctx: Context<CreatePool>) -> Result<()> {
-    //     Ok(())
-    // }
-
-    pub fn deposit(ctx: Context<Deposit>, deposit_amount: u64) -> Result<()> {
-        let deposits = (ctx.accounts.pool_authority.to_account_info().lamports() as u128)
-            .checked_add(2 * ctx.accounts.pool_authority_player_info.amount_in_games as u128) // doubled because have to assume won all outstanding games
-            .unwrap();
-        let lp_total = ctx.accounts.lp_token_mint.supply as u128;
-        let mint_amount_u128: u128;
-        if deposits != 0 {
-            mint_amount_u128 = (deposit_amount as u128)
-                .checked_mul(lp_total)
-                .unwrap()
-                .checked_div(deposits)
-                .unwrap();
-        } else if lp_total == 0 {
-            // first deposit so have rate be 1
-            mint_amount_u128 = deposit_amount as u128;
-        } else {
-            panic!("pool blew up no deposits allowed");
-        }
+    pub fn update_pool(ctx: Context<PoolAdmin>, risk: RiskParams) -> Result<()> {
+        ctx.accounts.pool.set_risk(risk)
+    }
 
-        let mint_amount = u64::try_from(mint_amount_u128).unwrap();
-        anchor_spl::token::mint_to(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                MintTo {
-                    mint: ctx.accounts.lp_token_mint.to_account_info(),
-                    to: ctx.accounts.user_lp_token_account.to_account_info(),
-                    authority: ctx.accounts.pool_authority.to_account_info(),
-                },
-                &[&[
-                    AUTHORITY_SEED.as_ref(),
-                    ctx.accounts.pool.key().as_ref(),
-                    &[*ctx.bumps.get("pool_authority").unwrap()],
-                ]],
-            ),
-            mint_amount,
-        )?;
+    pub fn set_bot_authority(ctx: Context<PoolAdmin>, bot_authority: Pubkey) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        emit!(BotAuthorityChangedEvent {
+            pool: pool.key(),
+            old_bot_authority: pool.bot_authority,
+            new_bot_authority: bot_authority,
+        });
+        let admin = pool.admin;
+        pool.set_roles(admin, bot_authority)?;
+
+        Ok(())
+    }
+
+    // nothing can sign for the default pubkey so the bot can't play until a
+    // new one is set
+    pub fn revoke_bot(ctx: Context<PoolAdmin>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        emit!(BotRevokedEvent {
+            pool: pool.key(),
+            bot_authority: pool.bot_authority,
+        });
+        pool.bot_authority = Pubkey::default();
+
+        Ok(())
+    }
+
+    // the new admin has to accept, so a typo can't lock the pool
+    pub fn transfer_admin(ctx: Context<PoolAdmin>, new_admin: Pubkey) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        pool.pending_admin = Some(new_admin);
+        emit!(AdminTransferStartedEvent {
+            pool: pool.key(),
+            admin: pool.admin,
+            pending_admin: new_admin,
+        });
+
+        Ok(())
+    }
+
+    pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        emit!(AdminTransferredEvent {
+            pool: pool.key(),
+            old_admin: pool.admin,
+            new_admin: ctx.accounts.new_admin.key(),
+        });
+        let bot_authority = pool.bot_authority;
+        pool.set_roles(ctx.accounts.new_admin.key(), bot_authority)?;
+        pool.pending_admin = None;
+
+        Ok(())
+    }
+
+    // queued until the pool's games have all settled, the lamports aren't
+    // part of the nav until then, see nav
+    pub fn deposit(ctx: Context<Deposit>, deposit_amount: u64, min_lp_out: u64) -> Result<()> {
         anchor_lang::system_program::transfer(
             CpiContext::new(
                 ctx.accounts.system_program.to_account_info(),
@@ -102,27 +173,109 @@ pub mod blp {
             deposit_amount,
         )?;
 
-        Ok(())
+        queue_deposit(
+            &mut ctx.accounts.pool,
+            &mut ctx.accounts.deposit_request,
+            ctx.accounts.user_authority.key(),
+            deposit_amount,
+            min_lp_out,
+        )
     }
 
-    pub fn withdraw(ctx: Context<Withdraw>, withdraw_amount: u64) -> Result<()> {
-        let deposits = ctx.accounts.pool_authority.to_account_info().lamports() as u128;
-        // not considering money in games because must assume lost all
-        let lp_total = ctx.accounts.lp_token_mint.supply as u128;
-        let sol_withdraw_amount_u128: u128;
-        if lp_total != 0 && deposits != 0 {
-            sol_withdraw_amount_u128 = (withdraw_amount as u128)
-                .checked_mul(deposits)
-                .unwrap()
-                .checked_div(lp_total)
-                .unwrap();
-        } else if deposits == 0 {
-            panic!("no money to withdraw, all stuck in games or pool blew up");
-        } else {
-            panic!("no lp tokens outstanding");
+    // anyone can convert a queued deposit to lp once the pool's games have
+    // all settled, at the same nav withdrawals are redeemed at
+    pub fn process_deposit(ctx: Context<ProcessDeposit>) -> Result<()> {
+        let nav = pool_nav(
+            &ctx.accounts.pool,
+            &ctx.accounts.pool_authority,
+            &ctx.accounts.pool_authority_player_info,
+        )?;
+        let lp_supply = ctx.accounts.pool.lp_supply(&ctx.accounts.lp_token_mint);
+        let request = &ctx.accounts.deposit_request;
+        let mint_amount = convert_deposit(&mut ctx.accounts.pool, nav, lp_supply, request)?;
+        let pool_key = ctx.accounts.pool.key();
+        let seeds: &[&[&[u8]]] = &[&[
+            AUTHORITY_SEED.as_ref(),
+            pool_key.as_ref(),
+            &[*ctx.bumps.get("pool_authority").unwrap()],
+        ]];
+
+        match mint_amount {
+            Some(mint_amount) => anchor_spl::token::mint_to(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    MintTo {
+                        mint: ctx.accounts.lp_token_mint.to_account_info(),
+                        to: ctx.accounts.user_lp_token_account.to_account_info(),
+                        authority: ctx.accounts.pool_authority.to_account_info(),
+                    },
+                    seeds,
+                ),
+                mint_amount,
+            ),
+            None => anchor_lang::system_program::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.pool_authority.to_account_info(),
+                        to: ctx.accounts.owner.to_account_info(),
+                    },
+                    seeds,
+                ),
+                request.amount,
+            ),
         }
+    }
+
+    // lp is burnt now and redeemed once the epoch is processed, at whatever
+    // it's worth with every game settled
+    pub fn request_withdrawal(ctx: Context<RequestWithdrawal>, lp_amount: u64) -> Result<()> {
+        anchor_spl::token::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.lp_token_mint.to_account_info(),
+                    from: ctx.accounts.user_lp_token_account.to_account_info(),
+                    authority: ctx.accounts.user_authority.to_account_info(),
+                },
+            ),
+            lp_amount,
+        )?;
+
+        let pool = &mut ctx.accounts.pool;
+        pool.pending_lp = pool
+            .pending_lp
+            .checked_add(lp_amount)
+            .ok_or(BlpError::MathOverflow)?;
+        let request = &mut ctx.accounts.withdrawal_request;
+        request.pool = pool.key();
+        request.owner = ctx.accounts.user_authority.key();
+        request.epoch = pool.epoch;
+        request.lp_amount = lp_amount;
+
+        Ok(())
+    }
+
+    // anyone can process an epoch once it's over and the pool's games have
+    // all settled, the bot can't start new ones in the meantime
+    pub fn process_epoch(ctx: Context<ProcessEpoch>) -> Result<()> {
+        let nav = pool_nav(
+            &ctx.accounts.pool,
+            &ctx.accounts.pool_authority,
+            &ctx.accounts.pool_authority_player_info,
+        )?;
+        let lp_supply = ctx.accounts.pool.lp_supply(&ctx.accounts.lp_token_mint);
+        redeem_epoch(
+            &mut ctx.accounts.pool,
+            &mut ctx.accounts.epoch,
+            nav,
+            lp_supply,
+        )
+    }
+
+    pub fn claim_withdrawal(ctx: Context<ClaimWithdrawal>) -> Result<()> {
+        let lamports = claim_amount(&ctx.accounts.withdrawal_request, &mut ctx.accounts.epoch)?;
 
-        let sol_withdraw_amount = u64::try_from(sol_withdraw_amount_u128).unwrap();
         anchor_lang::system_program::transfer(
             CpiContext::new_with_signer(
                 ctx.accounts.system_program.to_account_info(),
@@ -136,35 +289,272 @@ pub mod blp {
                     &[*ctx.bumps.get("pool_authority").unwrap()],
                 ]],
             ),
-            sol_withdraw_amount,
+            lamports,
         )?;
 
-        anchor_spl::token::burn(
+        // claims add up to exactly what was reserved for the epoch
+        let pool = &mut ctx.accounts.pool;
+        pool.reserved = pool
+            .reserved
+            .checked_sub(lamports)
+            .ok_or(BlpError::MathOverflow)?;
+
+        Ok(())
+    }
+
+    pub fn bot_play(ctx: Context<BotPlay>, choice: RPS) -> Result<()> {
+        let nav = pool_nav(
+            &ctx.accounts.pool,
+            &ctx.accounts.pool_authority,
+            &ctx.accounts.pool_authority_player_info,
+        )?;
+        start_game(
+            &mut ctx.accounts.pool,
+            nav,
+            &ctx.accounts.pool_authority_player_info,
+            native_mint::ID,
+            &ctx.accounts.game,
+        )?;
+
+        join_game(
+            CpiContext::new_with_signer(
+                ctx.accounts.rps_program.to_account_info(),
+                JoinGame {
+                    player: ctx.accounts.pool_authority.to_account_info(),
+                    player_info: ctx.accounts.pool_authority_player_info.to_account_info(),
+                    game: ctx.accounts.game.to_account_info(),
+                    config: ctx.accounts.rps_config.to_account_info(),
+                    game_authority: ctx.accounts.game_authority.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                },
+                &[&[
+                    AUTHORITY_SEED.as_ref(),
+                    ctx.accounts.pool.key().as_ref(),
+                    &[*ctx.bumps.get("pool_authority").unwrap()],
+                ]],
+            ),
+            choice,
+            None,
+        )?;
+
+        Ok(())
+    }
+
+    // token pools hold `mint` in a token account owned by the pool authority
+    // and play rps_token games for it, lp and withdrawals work the same as
+    // for sol pools only in tokens
+    pub fn create_token_pool(
+        ctx: Context<CreateTokenPool>,
+        seed: u64,
+        epoch_duration: u64,
+        risk: RiskParams,
+        admin: Pubkey,
+    ) -> Result<()> {
+        let mint_info =
+            token_interface::mint_info(&ctx.accounts.mint).map_err(|_| BlpError::InvalidMint)?;
+        ctx.accounts.pool.set_risk(risk)?;
+        ctx.accounts.pool.seed = seed;
+        ctx.accounts.pool.set_epoch_duration(epoch_duration)?;
+        ctx.accounts.pool.start_epoch(Clock::get()?.slot)?;
+        ctx.accounts.pool.authority = ctx.accounts.pool_authority.key();
+        ctx.accounts.pool.authority_player_info = ctx.accounts.pool_authority_player_info.key();
+        ctx.accounts
+            .pool
+            .set_roles(admin, ctx.accounts.bot_authority.key())?;
+        ctx.accounts.pool.lp_token_mint = ctx.accounts.lp_token_mint.key();
+        ctx.accounts.pool.mint = Some(ctx.accounts.mint.key());
+
+        // lp has the same decimals as what backs it
+        anchor_spl::token::initialize_mint2(
             CpiContext::new(
                 ctx.accounts.token_program.to_account_info(),
-                Burn {
+                InitializeMint2 {
                     mint: ctx.accounts.lp_token_mint.to_account_info(),
-                    from: ctx.accounts.user_lp_token_account.to_account_info(),
-                    authority: ctx.accounts.pool_authority.to_account_info(),
                 },
             ),
-            withdraw_amount,
+            mint_info.decimals,
+            &ctx.accounts.pool_authority.key(),
+            Some(&ctx.accounts.pool_authority.key()),
+        )?;
+
+        let pool_key = ctx.accounts.pool.key();
+        token_interface::create_token_account(
+            ctx.accounts.bot_authority.to_account_info(),
+            ctx.accounts.pool_token_account.to_account_info(),
+            ctx.accounts.mint.to_account_info(),
+            ctx.accounts.pool_authority.key,
+            ctx.accounts.mint_token_program.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            mint_info.account_len,
+            &[&[
+                TOKENS_SEED.as_ref(),
+                pool_key.as_ref(),
+                &[*ctx.bumps.get("pool_token_account").unwrap()],
+            ]],
+        )?;
+
+        // the pool authority pays for its player info, the creator covers
+        // the rent so none of the pool's funds go to it
+        let rent = Rent::get()?.minimum_balance(TokenPlayerInfo::space());
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.bot_authority.to_account_info(),
+                    to: ctx.accounts.pool_authority.to_account_info(),
+                },
+            ),
+            rent,
         )?;
+        rps_token::cpi::create_player_info(CpiContext::new_with_signer(
+            ctx.accounts.rps_token_program.to_account_info(),
+            rps_token::cpi::accounts::CreatePlayerInfo {
+                owner: ctx.accounts.pool_authority.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                player_info: ctx.accounts.pool_authority_player_info.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+            },
+            &[&[
+                AUTHORITY_SEED.as_ref(),
+                pool_key.as_ref(),
+                &[*ctx.bumps.get("pool_authority").unwrap()],
+            ]],
+        ))?;
 
         Ok(())
     }
 
-    pub fn bot_play(ctx: Context<BotPlay>, choice: RPS) -> Result<()> {
-        join_game(
-            CpiContext::new(
-                ctx.accounts.rps_program.to_account_info(),
-                JoinGame {
+    pub fn deposit_token(
+        ctx: Context<DepositToken>,
+        deposit_amount: u64,
+        min_lp_out: u64,
+    ) -> Result<()> {
+        // the user pays any transfer fee on top, lp is priced on what lands
+        let received = ctx.accounts.vault().deposit(
+            ctx.accounts.user_token_account.to_account_info(),
+            ctx.accounts.user_authority.to_account_info(),
+            deposit_amount,
+        )?;
+
+        queue_deposit(
+            &mut ctx.accounts.pool,
+            &mut ctx.accounts.deposit_request,
+            ctx.accounts.user_authority.key(),
+            received,
+            min_lp_out,
+        )
+    }
+
+    pub fn process_token_deposit(ctx: Context<ProcessTokenDeposit>) -> Result<()> {
+        let nav = token_pool_nav(
+            &ctx.accounts.pool,
+            &ctx.accounts.pool_token_account,
+            &ctx.accounts.pool_authority_player_info,
+        )?;
+        let lp_supply = ctx.accounts.pool.lp_supply(&ctx.accounts.lp_token_mint);
+        let request = &ctx.accounts.deposit_request;
+        let mint_amount = convert_deposit(&mut ctx.accounts.pool, nav, lp_supply, request)?;
+        let pool_key = ctx.accounts.pool.key();
+        let seeds: &[&[&[u8]]] = &[&[
+            AUTHORITY_SEED.as_ref(),
+            pool_key.as_ref(),
+            &[*ctx.bumps.get("pool_authority").unwrap()],
+        ]];
+
+        match mint_amount {
+            Some(mint_amount) => anchor_spl::token::mint_to(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    MintTo {
+                        mint: ctx.accounts.lp_token_mint.to_account_info(),
+                        to: ctx.accounts.user_lp_token_account.to_account_info(),
+                        authority: ctx.accounts.pool_authority.to_account_info(),
+                    },
+                    seeds,
+                ),
+                mint_amount,
+            ),
+            // the depositor pays any transfer fee on the way back too
+            None => ctx.accounts.vault().withdraw(
+                ctx.accounts.user_token_account.to_account_info(),
+                request.amount,
+                seeds,
+            ),
+        }
+    }
+
+    pub fn process_token_epoch(ctx: Context<ProcessTokenEpoch>) -> Result<()> {
+        let nav = token_pool_nav(
+            &ctx.accounts.pool,
+            &ctx.accounts.pool_token_account,
+            &ctx.accounts.pool_authority_player_info,
+        )?;
+        let lp_supply = ctx.accounts.pool.lp_supply(&ctx.accounts.lp_token_mint);
+        redeem_epoch(
+            &mut ctx.accounts.pool,
+            &mut ctx.accounts.epoch,
+            nav,
+            lp_supply,
+        )
+    }
+
+    pub fn claim_token_withdrawal(ctx: Context<ClaimTokenWithdrawal>) -> Result<()> {
+        let amount = claim_amount(&ctx.accounts.withdrawal_request, &mut ctx.accounts.epoch)?;
+
+        ctx.accounts.vault().withdraw(
+            ctx.accounts.user_token_account.to_account_info(),
+            amount,
+            &[&[
+                AUTHORITY_SEED.as_ref(),
+                ctx.accounts.pool.key().as_ref(),
+                &[*ctx.bumps.get("pool_authority").unwrap()],
+            ]],
+        )?;
+
+        let pool = &mut ctx.accounts.pool;
+        pool.reserved = pool
+            .reserved
+            .checked_sub(amount)
+            .ok_or(BlpError::MathOverflow)?;
+
+        Ok(())
+    }
+
+    pub fn bot_play_token(ctx: Context<BotPlayToken>, choice: RPS) -> Result<()> {
+        let nav = token_pool_nav(
+            &ctx.accounts.pool,
+            &ctx.accounts.pool_token_account,
+            &ctx.accounts.pool_authority_player_info,
+        )?;
+        start_game(
+            &mut ctx.accounts.pool,
+            nav,
+            &ctx.accounts.pool_authority_player_info,
+            ctx.accounts.game.mint,
+            &ctx.accounts.game,
+        )?;
+
+        rps_token::cpi::join_game(
+            CpiContext::new_with_signer(
+                ctx.accounts.rps_token_program.to_account_info(),
+                rps_token::cpi::accounts::JoinGame {
                     player: ctx.accounts.pool_authority.to_account_info(),
+                    player_token_account: ctx.accounts.pool_token_account.to_account_info(),
                     player_info: ctx.accounts.pool_authority_player_info.to_account_info(),
                     game: ctx.accounts.game.to_account_info(),
+                    config: ctx.accounts.rps_config.to_account_info(),
+                    paused_mint: ctx.accounts.paused_mint.to_account_info(),
                     game_authority: ctx.accounts.game_authority.to_account_info(),
+                    escrow_token_account: ctx.accounts.escrow_token_account.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    token_program: ctx.accounts.mint_token_program.to_account_info(),
                     system_program: ctx.accounts.system_program.to_account_info(),
                 },
+                &[&[
+                    AUTHORITY_SEED.as_ref(),
+                    ctx.accounts.pool.key().as_ref(),
+                    &[*ctx.bumps.get("pool_authority").unwrap()],
+                ]],
             ),
             choice,
             None,
@@ -174,6 +564,172 @@ pub mod blp {
     }
 }
 
+// lamports owed to processed withdrawals aren't the pool's anymore, queued
+// deposits aren't yet, and the pool authority's rent was never anyone's
+fn pool_nav(pool: &Pool, pool_authority: &AccountInfo, player_info: &PlayerInfo) -> Result<Nav> {
+    Ok(Nav {
+        liquid: pool_authority
+            .lamports()
+            .saturating_sub(Rent::get()?.minimum_balance(0))
+            .saturating_sub(pool.reserved)
+            .saturating_sub(pool.pending_deposits),
+        in_games: player_info.amount_in_games,
+    })
+}
+
+// same for token pools, only in tokens and with the stakes from the pool's
+// rps_token player info for the mint
+fn token_pool_nav(
+    pool: &Pool,
+    pool_token_account: &AccountInfo,
+    player_info: &TokenPlayerInfo,
+) -> Result<Nav> {
+    let balance = token_interface::token_account(pool_token_account)
+        .ok_or(ProgramError::InvalidAccountData)?
+        .amount;
+    Ok(Nav {
+        liquid: balance
+            .saturating_sub(pool.reserved)
+            .saturating_sub(pool.pending_deposits),
+        in_games: player_info.amount_in_games,
+    })
+}
+
+// everything the bot has to clear before joining `game` in `mint`, the game
+// counts as joined once it does
+fn start_game(
+    pool: &mut Pool,
+    nav: Nav,
+    stats: &PlayerStats,
+    mint: Pubkey,
+    game: &GameData,
+) -> Result<()> {
+    let wager = game.wager_amount;
+    // anything but a new game fails to join anyway
+    let best_of = match game.state {
+        GameState::AcceptingChallenge { config, .. } => config.best_of,
+        _ => 1,
+    };
+    // no new games until the epoch is processed, its nav has to settle
+    if Clock::get()?.slot >= pool.epoch_end_slot {
+        return err!(BlpError::EpochOver);
+    }
+    if wager > nav.liquid {
+        return err!(BlpError::InsufficientLiquidity);
+    }
+    pool.risk
+        .check(nav, pool.open_games(stats), mint, wager, best_of)?;
+    pool.games_joined += 1;
+    Ok(())
+}
+
+// `amount` has landed in the pool, it's held apart from the nav until it's
+// converted
+fn queue_deposit(
+    pool: &mut Account<Pool>,
+    request: &mut DepositRequest,
+    owner: Pubkey,
+    amount: u64,
+    min_lp_out: u64,
+) -> Result<()> {
+    pool.pending_deposits = pool
+        .pending_deposits
+        .checked_add(amount)
+        .ok_or(BlpError::MathOverflow)?;
+    request.pool = pool.key();
+    request.owner = owner;
+    request.epoch = pool.epoch;
+    request.amount = amount;
+    request.min_lp_out = min_lp_out;
+    Ok(())
+}
+
+// lp for a queued deposit, only with every game settled so it can't buy into
+// a result that's already known. the bot can't start games once the epoch is
+// over so this always comes around by the epoch boundary. none if it's less
+// than the depositor asked for, they get the deposit back instead
+fn convert_deposit(
+    pool: &mut Pool,
+    nav: Nav,
+    lp_supply: u64,
+    request: &DepositRequest,
+) -> Result<Option<u64>> {
+    if nav.in_games != 0 {
+        return err!(BlpError::GamesOpen);
+    }
+    let lp = match nav.lp_for_deposit(request.amount, lp_supply, request.min_lp_out) {
+        Ok(lp) => Some(lp),
+        Err(BlpError::LpBelowMinimum) => None,
+        Err(err) => return Err(err.into()),
+    };
+    pool.pending_deposits = pool
+        .pending_deposits
+        .checked_sub(request.amount)
+        .ok_or(BlpError::MathOverflow)?;
+    Ok(lp)
+}
+
+// redeems the epoch's pending lp at `nav`, in lamports or tokens depending
+// on the pool
+fn redeem_epoch(
+    pool: &mut Account<Pool>,
+    epoch: &mut Epoch,
+    nav: Nav,
+    lp_supply: u64,
+) -> Result<()> {
+    let slot = Clock::get()?.slot;
+    if slot < pool.epoch_end_slot {
+        return err!(BlpError::EpochNotOver);
+    }
+    if nav.in_games != 0 {
+        return err!(BlpError::GamesOpen);
+    }
+
+    let lamports = nav
+        .lamports_for_withdraw(pool.pending_lp, lp_supply)
+        .ok_or(BlpError::MathOverflow)?;
+
+    epoch.pool = pool.key();
+    epoch.index = pool.epoch;
+    epoch.lp_amount = pool.pending_lp;
+    epoch.lamports = lamports;
+
+    pool.reserved = pool
+        .reserved
+        .checked_add(lamports)
+        .ok_or(BlpError::MathOverflow)?;
+    pool.pending_lp = 0;
+    pool.epoch += 1;
+    pool.start_epoch(slot)?;
+
+    Ok(())
+}
+
+// the request's share of what its epoch was redeemed at, rounded down. the
+// last claim gets whatever rounding left over so nothing stays reserved
+fn claim_amount(request: &WithdrawalRequest, epoch: &mut Epoch) -> Result<u64> {
+    let claimed_lp = epoch
+        .claimed_lp
+        .checked_add(request.lp_amount)
+        .ok_or(BlpError::MathOverflow)?;
+    let amount = if claimed_lp == epoch.lp_amount {
+        epoch.lamports.checked_sub(epoch.claimed)
+    } else {
+        (request.lp_amount as u128)
+            .checked_mul(epoch.lamports as u128)
+            .and_then(|lamports| lamports.checked_div(epoch.lp_amount as u128))
+            .and_then(|lamports| u64::try_from(lamports).ok())
+    }
+    .ok_or(BlpError::MathOverflow)?;
+
+    epoch.claimed_lp = claimed_lp;
+    epoch.claimed = epoch
+        .claimed
+        .checked_add(amount)
+        .ok_or(BlpError::MathOverflow)?;
+    Ok(amount)
+}
+
 #[derive(Accounts)]
 #[instruction(pool_seed: u64)]
 pub struct CreatePool<'info> {
@@ -190,8 +746,9 @@ pub struct CreatePool<'info> {
     #[account(mut, seeds = [AUTHORITY_SEED.as_ref(), pool.key().as_ref()], bump)]
     pub pool_authority: AccountInfo<'info>,
 
+    /// CHECK: created by rps, which checks the seeds
     #[account(mut)]
-    pub pool_authority_player_info: Account<'info, PlayerInfo>,
+    pub pool_authority_player_info: AccountInfo<'info>,
 
     #[account(
         init,
@@ -213,8 +770,46 @@ pub struct CreatePool<'info> {
 #[derive(Accounts)]
 pub struct Deposit<'info> {
     #[account(
+        mut,
+        seeds = [POOL_SEED.as_ref(), &pool.seed.to_le_bytes()],
+        bump,
+        constraint = pool.mint.is_none() @ BlpError::WrongPoolMint,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    /// CHECK: this pda for the pool
+    #[account(mut, seeds = [AUTHORITY_SEED.as_ref(), pool.key().as_ref()], bump)]
+    pub pool_authority: AccountInfo<'info>,
+
+    // one request per user each epoch
+    #[account(
+        init,
+        seeds = [
+            DEPOSIT_SEED.as_ref(),
+            pool.key().as_ref(),
+            user_authority.key().as_ref(),
+            &pool.epoch.to_le_bytes(),
+        ],
+        bump,
+        payer = user_authority,
+        space = DepositRequest::space(),
+    )]
+    pub deposit_request: Account<'info, DepositRequest>,
+
+    // depositing from
+    #[account(mut)]
+    pub user_authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ProcessDeposit<'info> {
+    #[account(
+        mut,
         seeds = [POOL_SEED.as_ref(), &pool.seed.to_le_bytes()],
         bump,
+        constraint = pool.mint.is_none() @ BlpError::WrongPoolMint,
     )]
     pub pool: Account<'info, Pool>,
 
@@ -226,20 +821,34 @@ pub struct Deposit<'info> {
     pub pool_authority_player_info: Account<'info, PlayerInfo>,
 
     #[account(
+        mut,
         seeds = [MINT_SEED.as_ref(), pool.key().as_ref()],
         bump,
     )]
     pub lp_token_mint: Account<'info, Mint>,
 
-    // depositing from
-    #[account(mut)]
-    pub user_authority: Signer<'info>,
+    #[account(
+        mut,
+        close = owner,
+        seeds = [
+            DEPOSIT_SEED.as_ref(),
+            pool.key().as_ref(),
+            owner.key().as_ref(),
+            &deposit_request.epoch.to_le_bytes(),
+        ],
+        bump,
+    )]
+    pub deposit_request: Account<'info, DepositRequest>,
+
+    /// CHECK: who made the request, gets its rent back and refunds
+    #[account(mut, constraint = owner.key() == deposit_request.owner)]
+    pub owner: AccountInfo<'info>,
 
     // where to mint lp tokens to
     #[account(
         mut,
         constraint = user_lp_token_account.mint == lp_token_mint.key(),
-        constraint = user_lp_token_account.owner == user_authority.key(),
+        constraint = user_lp_token_account.owner == owner.key(),
     )]
     pub user_lp_token_account: Account<'info, TokenAccount>,
 
@@ -248,27 +857,36 @@ pub struct Deposit<'info> {
 }
 
 #[derive(Accounts)]
-pub struct Withdraw<'info> {
+pub struct RequestWithdrawal<'info> {
     #[account(
+        mut,
         seeds = [POOL_SEED.as_ref(), &pool.seed.to_le_bytes()],
         bump,
     )]
     pub pool: Account<'info, Pool>,
 
-    /// CHECK: this pda for the pool
-    #[account(mut, seeds = [AUTHORITY_SEED.as_ref(), pool.key().as_ref()], bump)]
-    pub pool_authority: AccountInfo<'info>,
-
-    #[account(constraint = pool_authority_player_info.owner.key() == pool_authority.key())]
-    pub pool_authority_player_info: Account<'info, PlayerInfo>,
+    // one request per user each epoch
+    #[account(
+        init,
+        seeds = [
+            WITHDRAWAL_SEED.as_ref(),
+            pool.key().as_ref(),
+            user_authority.key().as_ref(),
+            &pool.epoch.to_le_bytes(),
+        ],
+        bump,
+        payer = user_authority,
+        space = WithdrawalRequest::space(),
+    )]
+    pub withdrawal_request: Account<'info, WithdrawalRequest>,
 
     #[account(
+        mut,
         seeds = [MINT_SEED.as_ref(), pool.key().as_ref()],
         bump,
     )]
     pub lp_token_mint: Account<'info, Mint>,
 
-    // withdrawing to
     #[account(mut)]
     pub user_authority: Signer<'info>,
 
@@ -285,15 +903,17 @@ pub struct Withdraw<'info> {
 }
 
 #[derive(Accounts)]
-pub struct BotPlay<'info> {
+pub struct ProcessEpoch<'info> {
     #[account(
+        mut,
         seeds = [POOL_SEED.as_ref(), &pool.seed.to_le_bytes()],
         bump,
+        constraint = pool.mint.is_none() @ BlpError::WrongPoolMint,
     )]
     pub pool: Account<'info, Pool>,
 
     /// CHECK: this pda for the pool
-    #[account(mut, seeds = [AUTHORITY_SEED.as_ref(), pool.key().as_ref()], bump)]
+    #[account(seeds = [AUTHORITY_SEED.as_ref(), pool.key().as_ref()], bump)]
     pub pool_authority: AccountInfo<'info>,
 
     #[account(constraint = pool_authority_player_info.owner.key() == pool_authority.key())]
@@ -306,34 +926,544 @@ pub struct BotPlay<'info> {
     pub lp_token_mint: Account<'info, Mint>,
 
     #[account(
-        mut,
-        seeds = [b"game".as_ref(), &game.seed.to_le_bytes()],
+        init,
+        seeds = [EPOCH_SEED.as_ref(), pool.key().as_ref(), &pool.epoch.to_le_bytes()],
         bump,
+        payer = payer,
+        space = Epoch::space(),
     )]
-    pub game: Account<'info, Game>,
-    /// CHECK: pda for the game account
-    #[account(mut, seeds = [b"authority".as_ref(), game.key().as_ref()], bump)]
-    pub game_authority: AccountInfo<'info>,
+    pub epoch: Account<'info, Epoch>,
 
-    #[account(
-        mut,
-        constraint = pool.bot_authority == bot_authority.key()
-    )]
-    pub bot_authority: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
 
-    pub rps_program: Program<'info, Rps>,
-    pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
 }
 
-#[account]
-#[derive()]
-pub struct Pool {
-    pub seed: u64,
-
-    // will be native sol only for now so no need for a token account
-    pub authority: Pubkey,
-
+#[derive(Accounts)]
+pub struct ClaimWithdrawal<'info> {
+    #[account(
+        mut,
+        seeds = [POOL_SEED.as_ref(), &pool.seed.to_le_bytes()],
+        bump,
+        constraint = pool.mint.is_none() @ BlpError::WrongPoolMint,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    /// CHECK: this pda for the pool
+    #[account(mut, seeds = [AUTHORITY_SEED.as_ref(), pool.key().as_ref()], bump)]
+    pub pool_authority: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        close = user_authority,
+        seeds = [
+            WITHDRAWAL_SEED.as_ref(),
+            pool.key().as_ref(),
+            user_authority.key().as_ref(),
+            &withdrawal_request.epoch.to_le_bytes(),
+        ],
+        bump,
+    )]
+    pub withdrawal_request: Account<'info, WithdrawalRequest>,
+
+    // only exists once the request's epoch has been processed
+    #[account(
+        mut,
+        seeds = [
+            EPOCH_SEED.as_ref(),
+            pool.key().as_ref(),
+            &withdrawal_request.epoch.to_le_bytes(),
+        ],
+        bump,
+    )]
+    pub epoch: Account<'info, Epoch>,
+
+    #[account(mut)]
+    pub user_authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PoolAdmin<'info> {
+    #[account(
+        mut,
+        seeds = [POOL_SEED.as_ref(), &pool.seed.to_le_bytes()],
+        bump,
+        has_one = admin @ BlpError::NotAdmin,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAdmin<'info> {
+    #[account(
+        mut,
+        seeds = [POOL_SEED.as_ref(), &pool.seed.to_le_bytes()],
+        bump,
+        constraint = pool.pending_admin == Some(new_admin.key()) @ BlpError::NotPendingAdmin,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    pub new_admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct BotPlay<'info> {
+    #[account(
+        mut,
+        seeds = [POOL_SEED.as_ref(), &pool.seed.to_le_bytes()],
+        bump,
+        constraint = pool.mint.is_none() @ BlpError::WrongPoolMint,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    /// CHECK: this pda for the pool
+    #[account(mut, seeds = [AUTHORITY_SEED.as_ref(), pool.key().as_ref()], bump)]
+    pub pool_authority: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        constraint = pool_authority_player_info.owner.key() == pool_authority.key()
+    )]
+    pub pool_authority_player_info: Account<'info, PlayerInfo>,
+
+    #[account(
+        seeds = [MINT_SEED.as_ref(), pool.key().as_ref()],
+        bump,
+    )]
+    pub lp_token_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"game".as_ref(), &game.seed.to_le_bytes()],
+        bump,
+        seeds::program = rps_program.key(),
+    )]
+    pub game: Account<'info, Game>,
+    // rps checks the seeds and pause flag
+    pub rps_config: Account<'info, Config>,
+    /// CHECK: pda for the game account
+    #[account(
+        mut,
+        seeds = [b"authority".as_ref(), game.key().as_ref()],
+        bump,
+        seeds::program = rps_program.key(),
+    )]
+    pub game_authority: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        constraint = pool.bot_authority == bot_authority.key()
+    )]
+    pub bot_authority: Signer<'info>,
+
+    pub rps_program: Program<'info, Rps>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(pool_seed: u64)]
+pub struct CreateTokenPool<'info> {
+    #[account(
+        init,
+        seeds = [POOL_SEED.as_ref(), &pool_seed.to_le_bytes()],
+        bump,
+        payer = bot_authority,
+        space = Pool::space()
+    )]
+    pub pool: Account<'info, Pool>,
+
+    /// CHECK: this a pda for the pool
+    #[account(mut, seeds = [AUTHORITY_SEED.as_ref(), pool.key().as_ref()], bump)]
+    pub pool_authority: AccountInfo<'info>,
+
+    /// CHECK: created by rps_token, which checks the seeds
+    #[account(mut)]
+    pub pool_authority_player_info: AccountInfo<'info>,
+
+    #[account(
+        init,
+        seeds = [MINT_SEED.as_ref(), pool.key().as_ref()],
+        bump,
+        payer = bot_authority,
+        space = Mint::LEN,
+    )]
+    pub lp_token_mint: Account<'info, Mint>,
+
+    /// CHECK: any mint of either token program, checked in the handler
+    pub mint: AccountInfo<'info>,
+
+    /// CHECK: created in the handler, owned by the pool authority
+    #[account(mut, seeds = [TOKENS_SEED.as_ref(), pool.key().as_ref()], bump)]
+    pub pool_token_account: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub bot_authority: Signer<'info>,
+
+    // for the lp mint
+    pub token_program: Program<'info, Token>,
+    /// CHECK: whichever token program owns the mint
+    #[account(
+        constraint = token_interface::is_token_program(mint_token_program.key) && mint_token_program.key() == *mint.owner
+            @ BlpError::InvalidTokenProgram
+    )]
+    pub mint_token_program: AccountInfo<'info>,
+    pub rps_token_program: Program<'info, RpsToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositToken<'info> {
+    #[account(
+        mut,
+        seeds = [POOL_SEED.as_ref(), &pool.seed.to_le_bytes()],
+        bump,
+        constraint = pool.mint == Some(mint.key()) @ BlpError::WrongPoolMint,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    /// CHECK: this pda for the pool
+    #[account(seeds = [AUTHORITY_SEED.as_ref(), pool.key().as_ref()], bump)]
+    pub pool_authority: AccountInfo<'info>,
+
+    /// CHECK: only ever created by create_token_pool
+    #[account(mut, seeds = [TOKENS_SEED.as_ref(), pool.key().as_ref()], bump)]
+    pub pool_token_account: AccountInfo<'info>,
+
+    // one request per user each epoch
+    #[account(
+        init,
+        seeds = [
+            DEPOSIT_SEED.as_ref(),
+            pool.key().as_ref(),
+            user_authority.key().as_ref(),
+            &pool.epoch.to_le_bytes(),
+        ],
+        bump,
+        payer = user_authority,
+        space = DepositRequest::space(),
+    )]
+    pub deposit_request: Account<'info, DepositRequest>,
+
+    /// CHECK: the pool's mint
+    pub mint: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub user_authority: Signer<'info>,
+
+    /// CHECK: depositing from, checked to be the user's token account for the
+    /// pool's mint
+    #[account(
+        mut,
+        constraint = token_interface::is_token_account(&user_token_account, &user_authority.key(), &mint.key())
+            @ BlpError::InvalidTokenAccount
+    )]
+    pub user_token_account: AccountInfo<'info>,
+
+    /// CHECK: whichever token program owns the mint
+    #[account(
+        constraint = token_interface::is_token_program(mint_token_program.key) && mint_token_program.key() == *mint.owner
+            @ BlpError::InvalidTokenProgram
+    )]
+    pub mint_token_program: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> DepositToken<'info> {
+    fn vault(&self) -> TokenEscrow<'info> {
+        TokenEscrow {
+            escrow_token_account: self.pool_token_account.to_account_info(),
+            mint: self.mint.to_account_info(),
+            game_authority: self.pool_authority.to_account_info(),
+            token_program: self.mint_token_program.to_account_info(),
+            system_program: self.system_program.to_account_info(),
+        }
+    }
+}
+
+#[derive(Accounts)]
+pub struct ProcessTokenDeposit<'info> {
+    #[account(
+        mut,
+        seeds = [POOL_SEED.as_ref(), &pool.seed.to_le_bytes()],
+        bump,
+        constraint = pool.mint == Some(pool_authority_player_info.mint) @ BlpError::WrongPoolMint,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    /// CHECK: this pda for the pool
+    #[account(seeds = [AUTHORITY_SEED.as_ref(), pool.key().as_ref()], bump)]
+    pub pool_authority: AccountInfo<'info>,
+
+    /// CHECK: only ever created by create_token_pool
+    #[account(mut, seeds = [TOKENS_SEED.as_ref(), pool.key().as_ref()], bump)]
+    pub pool_token_account: AccountInfo<'info>,
+
+    #[account(constraint = pool_authority_player_info.owner == pool_authority.key())]
+    pub pool_authority_player_info: Account<'info, TokenPlayerInfo>,
+
+    #[account(
+        mut,
+        seeds = [MINT_SEED.as_ref(), pool.key().as_ref()],
+        bump,
+    )]
+    pub lp_token_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        close = owner,
+        seeds = [
+            DEPOSIT_SEED.as_ref(),
+            pool.key().as_ref(),
+            owner.key().as_ref(),
+            &deposit_request.epoch.to_le_bytes(),
+        ],
+        bump,
+    )]
+    pub deposit_request: Account<'info, DepositRequest>,
+
+    /// CHECK: who made the request, gets its rent back
+    #[account(mut, constraint = owner.key() == deposit_request.owner)]
+    pub owner: AccountInfo<'info>,
+
+    // where to mint lp tokens to
+    #[account(
+        mut,
+        constraint = user_lp_token_account.mint == lp_token_mint.key(),
+        constraint = user_lp_token_account.owner == owner.key(),
+    )]
+    pub user_lp_token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: the pool's mint
+    #[account(constraint = pool.mint == Some(mint.key()) @ BlpError::WrongPoolMint)]
+    pub mint: AccountInfo<'info>,
+
+    /// CHECK: refunds go here, checked to be the owner's token account for
+    /// the pool's mint
+    #[account(
+        mut,
+        constraint = token_interface::is_token_account(&user_token_account, &owner.key(), &mint.key())
+            @ BlpError::InvalidTokenAccount
+    )]
+    pub user_token_account: AccountInfo<'info>,
+
+    // for the lp mint
+    pub token_program: Program<'info, Token>,
+    /// CHECK: whichever token program owns the mint
+    #[account(
+        constraint = token_interface::is_token_program(mint_token_program.key) && mint_token_program.key() == *mint.owner
+            @ BlpError::InvalidTokenProgram
+    )]
+    pub mint_token_program: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ProcessTokenDeposit<'info> {
+    fn vault(&self) -> TokenEscrow<'info> {
+        TokenEscrow {
+            escrow_token_account: self.pool_token_account.to_account_info(),
+            mint: self.mint.to_account_info(),
+            game_authority: self.pool_authority.to_account_info(),
+            token_program: self.mint_token_program.to_account_info(),
+            system_program: self.system_program.to_account_info(),
+        }
+    }
+}
+
+#[derive(Accounts)]
+pub struct ProcessTokenEpoch<'info> {
+    #[account(
+        mut,
+        seeds = [POOL_SEED.as_ref(), &pool.seed.to_le_bytes()],
+        bump,
+        constraint = pool.mint == Some(pool_authority_player_info.mint) @ BlpError::WrongPoolMint,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    /// CHECK: only ever created by create_token_pool
+    #[account(seeds = [TOKENS_SEED.as_ref(), pool.key().as_ref()], bump)]
+    pub pool_token_account: AccountInfo<'info>,
+
+    #[account(constraint = pool_authority_player_info.owner == pool.authority)]
+    pub pool_authority_player_info: Account<'info, TokenPlayerInfo>,
+
+    #[account(
+        seeds = [MINT_SEED.as_ref(), pool.key().as_ref()],
+        bump,
+    )]
+    pub lp_token_mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        seeds = [EPOCH_SEED.as_ref(), pool.key().as_ref(), &pool.epoch.to_le_bytes()],
+        bump,
+        payer = payer,
+        space = Epoch::space(),
+    )]
+    pub epoch: Account<'info, Epoch>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimTokenWithdrawal<'info> {
+    #[account(
+        mut,
+        seeds = [POOL_SEED.as_ref(), &pool.seed.to_le_bytes()],
+        bump,
+        constraint = pool.mint == Some(mint.key()) @ BlpError::WrongPoolMint,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    /// CHECK: this pda for the pool
+    #[account(seeds = [AUTHORITY_SEED.as_ref(), pool.key().as_ref()], bump)]
+    pub pool_authority: AccountInfo<'info>,
+
+    /// CHECK: only ever created by create_token_pool
+    #[account(mut, seeds = [TOKENS_SEED.as_ref(), pool.key().as_ref()], bump)]
+    pub pool_token_account: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        close = user_authority,
+        seeds = [
+            WITHDRAWAL_SEED.as_ref(),
+            pool.key().as_ref(),
+            user_authority.key().as_ref(),
+            &withdrawal_request.epoch.to_le_bytes(),
+        ],
+        bump,
+    )]
+    pub withdrawal_request: Account<'info, WithdrawalRequest>,
+
+    // only exists once the request's epoch has been processed
+    #[account(
+        mut,
+        seeds = [
+            EPOCH_SEED.as_ref(),
+            pool.key().as_ref(),
+            &withdrawal_request.epoch.to_le_bytes(),
+        ],
+        bump,
+    )]
+    pub epoch: Account<'info, Epoch>,
+
+    /// CHECK: the pool's mint
+    pub mint: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub user_authority: Signer<'info>,
+
+    /// CHECK: paying out to, checked to be the user's token account for the
+    /// pool's mint
+    #[account(
+        mut,
+        constraint = token_interface::is_token_account(&user_token_account, &user_authority.key(), &mint.key())
+            @ BlpError::InvalidTokenAccount
+    )]
+    pub user_token_account: AccountInfo<'info>,
+
+    /// CHECK: whichever token program owns the mint
+    #[account(
+        constraint = token_interface::is_token_program(mint_token_program.key) && mint_token_program.key() == *mint.owner
+            @ BlpError::InvalidTokenProgram
+    )]
+    pub mint_token_program: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ClaimTokenWithdrawal<'info> {
+    fn vault(&self) -> TokenEscrow<'info> {
+        TokenEscrow {
+            escrow_token_account: self.pool_token_account.to_account_info(),
+            mint: self.mint.to_account_info(),
+            game_authority: self.pool_authority.to_account_info(),
+            token_program: self.mint_token_program.to_account_info(),
+            system_program: self.system_program.to_account_info(),
+        }
+    }
+}
+
+#[derive(Accounts)]
+pub struct BotPlayToken<'info> {
+    #[account(
+        mut,
+        seeds = [POOL_SEED.as_ref(), &pool.seed.to_le_bytes()],
+        bump,
+        constraint = pool.mint == Some(mint.key()) @ BlpError::WrongPoolMint,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    /// CHECK: this pda for the pool
+    #[account(mut, seeds = [AUTHORITY_SEED.as_ref(), pool.key().as_ref()], bump)]
+    pub pool_authority: AccountInfo<'info>,
+
+    /// CHECK: only ever created by create_token_pool
+    #[account(mut, seeds = [TOKENS_SEED.as_ref(), pool.key().as_ref()], bump)]
+    pub pool_token_account: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        constraint = pool_authority_player_info.owner == pool_authority.key(),
+        constraint = pool_authority_player_info.mint == mint.key(),
+    )]
+    pub pool_authority_player_info: Account<'info, TokenPlayerInfo>,
+
+    #[account(
+        mut,
+        seeds = [b"game".as_ref(), &game.seed.to_le_bytes()],
+        bump,
+        seeds::program = rps_token_program.key(),
+    )]
+    pub game: Box<Account<'info, TokenGame>>,
+    // rps_token checks the seeds and pause flags
+    pub rps_config: Account<'info, TokenConfig>,
+    /// CHECK: checked by rps_token
+    pub paused_mint: AccountInfo<'info>,
+    /// CHECK: pda for the game account
+    #[account(
+        mut,
+        seeds = [b"authority".as_ref(), game.key().as_ref()],
+        bump,
+        seeds::program = rps_token_program.key(),
+    )]
+    pub game_authority: AccountInfo<'info>,
+    /// CHECK: checked by rps_token
+    #[account(mut)]
+    pub escrow_token_account: AccountInfo<'info>,
+
+    /// CHECK: the game's mint, has to be the pool's
+    #[account(mut, address = game.mint)]
+    pub mint: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        constraint = pool.bot_authority == bot_authority.key()
+    )]
+    pub bot_authority: Signer<'info>,
+
+    pub rps_token_program: Program<'info, RpsToken>,
+    /// CHECK: checked by rps_token
+    pub mint_token_program: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[account]
+#[derive()]
+pub struct Pool {
+    pub seed: u64,
+
+    // holds a sol pool's lamports, owns a token pool's token account
+    pub authority: Pubkey,
+
     // rps player info for the pool (needed so we can see outstanding wager)
     pub authority_player_info: Pubkey,
 
@@ -342,6 +1472,42 @@ pub struct Pool {
 
     // lp token mint for pool depositors
     pub lp_token_mint: Pubkey,
+
+    // slots between withdrawal redemptions
+    pub epoch_duration: u64,
+
+    // withdrawals requested now are redeemed when this epoch is processed
+    pub epoch: u64,
+
+    // the bot can't start games from this slot on until the epoch is
+    // processed
+    pub epoch_end_slot: u64,
+
+    // burnt by withdrawal requests waiting on the current epoch, still part
+    // of the supply until they're redeemed
+    pub pending_lp: u64,
+
+    // lamports owed to processed withdrawals that haven't been claimed
+    pub reserved: u64,
+
+    // lamports deposited but not converted to lp yet, not the pool's until
+    // they are
+    pub pending_deposits: u64,
+
+    // every game the bot has joined, the ones that haven't been recorded as
+    // won, drawn or lost in rps are still open
+    pub games_joined: u64,
+
+    pub risk: RiskParams,
+
+    // sets the risk params and the bot authority
+    pub admin: Pubkey,
+
+    // proposed by the admin, takes over once it accepts
+    pub pending_admin: Option<Pubkey>,
+
+    // what token pools hold and play for, none for sol pools
+    pub mint: Option<Pubkey>,
 }
 
 impl Pool {
@@ -349,4 +1515,198 @@ impl Pool {
         // idk lmao
         1000
     }
+    pub fn lp_supply(&self, lp_token_mint: &Mint) -> u64 {
+        lp_token_mint.supply + self.pending_lp
+    }
+    pub fn open_games(&self, stats: &PlayerStats) -> u64 {
+        self.games_joined
+            .saturating_sub(stats.games_won)
+            .saturating_sub(stats.games_drawn)
+            .saturating_sub(stats.games_lost)
+    }
+    pub fn set_epoch_duration(&mut self, epoch_duration: u64) -> Result<()> {
+        // a 0 slot epoch is over before the bot can play
+        if !(1..=MAX_EPOCH_DURATION).contains(&epoch_duration) {
+            return err!(BlpError::InvalidEpochDuration);
+        }
+        self.epoch_duration = epoch_duration;
+        Ok(())
+    }
+    pub fn start_epoch(&mut self, slot: u64) -> Result<()> {
+        self.epoch_end_slot = slot
+            .checked_add(self.epoch_duration)
+            .ok_or(BlpError::MathOverflow)?;
+        Ok(())
+    }
+    // a leaked bot key mustn't be able to take the pool over too
+    pub fn set_roles(&mut self, admin: Pubkey, bot_authority: Pubkey) -> Result<()> {
+        if admin == bot_authority {
+            return err!(BlpError::AdminIsBot);
+        }
+        self.admin = admin;
+        self.bot_authority = bot_authority;
+        Ok(())
+    }
+    pub fn set_risk(&mut self, risk: RiskParams) -> Result<()> {
+        if !risk.is_valid() {
+            return err!(BlpError::InvalidRiskParams);
+        }
+        self.risk = risk;
+        Ok(())
+    }
+}
+
+#[account]
+pub struct WithdrawalRequest {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub epoch: u64,
+    pub lp_amount: u64,
+}
+
+impl WithdrawalRequest {
+    pub fn space() -> usize {
+        8 + 32 + 32 + 8 + 8
+    }
+}
+
+// converted to lp by process_deposit once the pool's games have settled
+#[account]
+pub struct DepositRequest {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    // the epoch it was made in
+    pub epoch: u64,
+    // tokens for token pools, after any transfer fee
+    pub amount: u64,
+    // refunded instead if it comes to less lp than this
+    pub min_lp_out: u64,
+}
+
+impl DepositRequest {
+    pub fn space() -> usize {
+        8 + 32 + 32 + 8 + 8 + 8
+    }
+}
+
+// what an epoch's withdrawals were redeemed at
+#[account]
+pub struct Epoch {
+    pub pool: Pubkey,
+    pub index: u64,
+    pub lp_amount: u64,
+    // tokens for token pools
+    pub lamports: u64,
+    // how much of the above has been claimed so far
+    pub claimed_lp: u64,
+    pub claimed: u64,
+}
+
+impl Epoch {
+    pub fn space() -> usize {
+        8 + 32 + 8 + 8 + 8 + 8 + 8
+    }
+}
+
+#[event]
+pub struct BotAuthorityChangedEvent {
+    pool: Pubkey,
+    old_bot_authority: Pubkey,
+    new_bot_authority: Pubkey,
+}
+
+#[event]
+pub struct BotRevokedEvent {
+    pool: Pubkey,
+    bot_authority: Pubkey,
+}
+
+#[event]
+pub struct AdminTransferStartedEvent {
+    pool: Pubkey,
+    admin: Pubkey,
+    pending_admin: Pubkey,
+}
+
+#[event]
+pub struct AdminTransferredEvent {
+    pool: Pubkey,
+    old_admin: Pubkey,
+    new_admin: Pubkey,
+}
+
+#[error_code]
+pub enum BlpError {
+    #[msg("Math Overflow")]
+    MathOverflow,
+    #[msg("Pool has lp tokens outstanding but nothing backing them")]
+    PoolInsolvent,
+    #[msg("Not enough lamports in the pool, the rest is in games")]
+    InsufficientLiquidity,
+    #[msg("Epoch isn't over yet")]
+    EpochNotOver,
+    #[msg("Pool still has games open")]
+    GamesOpen,
+    #[msg("Epoch is over, no new games until it's processed")]
+    EpochOver,
+    #[msg("Invalid risk params")]
+    InvalidRiskParams,
+    #[msg("Pool doesn't play for this mint")]
+    MintNotAllowed,
+    #[msg("Wager above the pool's max wager")]
+    WagerTooLarge,
+    #[msg("Pool has too many games open")]
+    TooManyOpenGames,
+    #[msg("Game would put too much of the pool in games")]
+    TooMuchInGames,
+    #[msg("Only the pool's admin can do this")]
+    NotAdmin,
+    #[msg("Only the pending admin can accept")]
+    NotPendingAdmin,
+    #[msg("Pool holds a different asset")]
+    WrongPoolMint,
+    #[msg("Mint can't be pooled")]
+    InvalidMint,
+    #[msg("Token program doesn't own the mint")]
+    InvalidTokenProgram,
+    #[msg("Not the user's token account for the pool's mint")]
+    InvalidTokenAccount,
+    #[msg("Pool only plays single round games")]
+    MatchNotSupported,
+    #[msg("Epoch duration out of bounds")]
+    InvalidEpochDuration,
+    #[msg("Deposit comes to less lp than the minimum")]
+    LpBelowMinimum,
+    #[msg("Admin and bot authority have to be different keys")]
+    AdminIsBot,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_claims_add_up_to_epoch() {
+        let mut epoch = Epoch {
+            pool: Pubkey::default(),
+            index: 0,
+            lp_amount: 3_000,
+            lamports: 1_000,
+            claimed_lp: 0,
+            claimed: 0,
+        };
+        let request = |lp_amount| WithdrawalRequest {
+            pool: Pubkey::default(),
+            owner: Pubkey::default(),
+            epoch: 0,
+            lp_amount,
+        };
+
+        // each of the first two rounds down from 333.33
+        assert_eq!(claim_amount(&request(1_000), &mut epoch).unwrap(), 333);
+        assert_eq!(claim_amount(&request(1_000), &mut epoch).unwrap(), 333);
+        // the last one gets the dust
+        assert_eq!(claim_amount(&request(1_000), &mut epoch).unwrap(), 334);
+        assert_eq!(epoch.claimed, epoch.lamports);
+    }
 }
@@ -0,0 +1,247 @@
+//! What a pool is worth and what its lp tokens trade at. Deposits and
+//! withdrawals are both queued and priced off the same `Nav` so there's no
+//! spread between them to arbitrage, and only once every game has settled: a
+//! game's result is public before anyone settles it, so pricing against an
+//! open stake lets a depositor buy into a win that's already decided.
+//! Queued deposits aren't part of the `Nav` until they're converted.
+
+use crate::BlpError;
+
+/// Lamports the pool holds plus its stakes in open games. Player 2's share
+/// of the fee isn't counted until it's paid out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Nav {
+    pub liquid: u64,
+    pub in_games: u64,
+}
+
+impl Nav {
+    pub fn total(self) -> u128 {
+        self.liquid as u128 + self.in_games as u128
+    }
+
+    /// Lp tokens minted for depositing `amount`, rounded down so the pool
+    /// never pays for rounding. Fails while the pool has lp tokens
+    /// outstanding with nothing backing them, or on less than `min_lp_out`
+    /// or no lp at all: lamports sent straight to the pool push the price up
+    /// until small deposits round down to nothing and go to whoever holds the
+    /// lp already.
+    pub fn lp_for_deposit(
+        self,
+        amount: u64,
+        lp_supply: u64,
+        min_lp_out: u64,
+    ) -> Result<u64, BlpError> {
+        // first deposit sets the rate at 1 lp per lamport
+        let lp = if lp_supply == 0 {
+            amount
+        } else {
+            if self.total() == 0 {
+                return Err(BlpError::PoolInsolvent);
+            }
+            (amount as u128)
+                .checked_mul(lp_supply as u128)
+                .and_then(|lp| lp.checked_div(self.total()))
+                .and_then(|lp| u64::try_from(lp).ok())
+                .ok_or(BlpError::MathOverflow)?
+        };
+        if lp == 0 || lp < min_lp_out {
+            return Err(BlpError::LpBelowMinimum);
+        }
+        Ok(lp)
+    }
+
+    /// Lamports `lp_amount` lp tokens are worth, also rounded down. Only
+    /// `liquid` can actually be paid out.
+    pub fn lamports_for_withdraw(self, lp_amount: u64, lp_supply: u64) -> Option<u64> {
+        if lp_amount > lp_supply {
+            return None;
+        }
+        if lp_supply == 0 {
+            return Some(0);
+        }
+        let lamports = (lp_amount as u128)
+            .checked_mul(self.total())?
+            .checked_div(lp_supply as u128)?;
+        u64::try_from(lamports).ok()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Clone, Copy)]
+    enum Outcome {
+        Won,
+        Lost,
+        Tied,
+    }
+
+    // the pool's stake coming back from a settled game
+    fn settle(nav: Nav, stake: u64, outcome: Outcome) -> Nav {
+        let returned = match outcome {
+            Outcome::Won => 2 * stake,
+            Outcome::Lost => 0,
+            Outcome::Tied => stake,
+        };
+        Nav {
+            liquid: nav.liquid + returned,
+            in_games: nav.in_games - stake,
+        }
+    }
+
+    #[test]
+    fn test_first_deposit() {
+        let nav = Nav {
+            liquid: 0,
+            in_games: 0,
+        };
+        assert!(matches!(nav.lp_for_deposit(1_000, 0, 0), Ok(1_000)));
+        // lp outstanding with nothing left means the pool blew up
+        assert!(matches!(
+            nav.lp_for_deposit(1_000, 10, 0),
+            Err(BlpError::PoolInsolvent)
+        ));
+        assert_eq!(nav.lamports_for_withdraw(11, 10), None);
+    }
+
+    #[test]
+    fn test_deposit_withdraw_same_price() {
+        let nav = Nav {
+            liquid: 10_000,
+            in_games: 0,
+        };
+        let supply = 8_000;
+
+        let lp = nav.lp_for_deposit(1_000, supply, 0).unwrap();
+        assert_eq!(lp, 800);
+        let nav = Nav {
+            liquid: nav.liquid + 1_000,
+            ..nav
+        };
+        // withdrawing straight away gets the deposit back
+        assert_eq!(nav.lamports_for_withdraw(lp, supply + lp), Some(1_000));
+    }
+
+    #[test]
+    fn test_rounding_favors_pool() {
+        let nav = Nav {
+            liquid: 10_003,
+            in_games: 0,
+        };
+        let supply = 7_777;
+        for amount in [2, 3, 999, 12_345] {
+            let lp = nav.lp_for_deposit(amount, supply, 0).unwrap();
+            let after = Nav {
+                liquid: nav.liquid + amount,
+                ..nav
+            };
+            let out = after.lamports_for_withdraw(lp, supply + lp).unwrap();
+            assert!(out <= amount);
+
+            // everyone else's lp is worth at least as much as before
+            let rest = after.lamports_for_withdraw(supply, supply + lp).unwrap();
+            assert!(rest as u128 >= nav.total());
+        }
+    }
+
+    #[test]
+    fn test_no_deposits_for_nothing() {
+        // first depositor puts in 1 lamport then sends the pool a lot more
+        // directly, so the next deposit rounds down to no lp
+        let nav = Nav {
+            liquid: 1 + 1_000_000,
+            in_games: 0,
+        };
+        let supply = 1;
+        assert!(matches!(
+            nav.lp_for_deposit(1_000_000, supply, 0),
+            Err(BlpError::LpBelowMinimum)
+        ));
+        // or to less than the depositor would take
+        assert!(matches!(
+            nav.lp_for_deposit(2_500_000, supply, 3),
+            Err(BlpError::LpBelowMinimum)
+        ));
+        assert!(matches!(nav.lp_for_deposit(2_500_000, supply, 2), Ok(2)));
+        let empty = Nav {
+            liquid: 0,
+            in_games: 0,
+        };
+        assert!(matches!(
+            empty.lp_for_deposit(0, 0, 0),
+            Err(BlpError::LpBelowMinimum)
+        ));
+    }
+
+    #[test]
+    fn test_pending_withdrawals() {
+        let nav = Nav {
+            liquid: 6_000,
+            in_games: 4_000,
+        };
+        // 4_000 of the 10_000 lp were burnt by withdrawal requests, they
+        // still count towards the supply until the epoch is processed
+        let supply = 10_000;
+        let pending = 4_000;
+
+        // the game settles before the epoch is processed, requests get their
+        // share of whatever it came to
+        for (liquid, redeemed) in [(14_000, 5_600), (6_000, 2_400), (10_000, 4_000)] {
+            let settled = Nav {
+                liquid,
+                in_games: 0,
+            };
+            assert_eq!(
+                settled.lamports_for_withdraw(pending, supply),
+                Some(redeemed)
+            );
+            // and deposits in the meantime are priced the same as before the
+            // requests
+            assert_eq!(settled.lp_for_deposit(liquid, supply, 0).ok(), Some(supply));
+        }
+        assert_eq!(nav.lamports_for_withdraw(0, 0), Some(0));
+    }
+
+    #[test]
+    fn test_no_value_from_round_trips_around_settlement() {
+        let stake = 4_000;
+        let nav = Nav {
+            liquid: 6_000,
+            in_games: stake,
+        };
+        let supply = 10_000;
+        let amount = 10_000;
+
+        // the result is public once both players revealed, whoever knows it
+        // can queue a deposit or a withdrawal before the game settles
+        for outcome in [Outcome::Won, Outcome::Lost, Outcome::Tied] {
+            // the deposit sits outside the nav until it's converted after
+            // the game settles, so it gets none of the result
+            let settled = settle(nav, stake, outcome);
+            let lp = settled.lp_for_deposit(amount, supply, 0).unwrap();
+            let with_deposit = Nav {
+                liquid: settled.liquid + amount,
+                ..settled
+            };
+            // and withdrawing it again at the next epoch gets at most the
+            // deposit back
+            let out = with_deposit.lamports_for_withdraw(lp, supply + lp).unwrap();
+            assert!(out <= amount);
+            let others = with_deposit.total() - out as u128;
+            assert!(others >= settled.total());
+
+            // withdrawing ahead of a loss doesn't dodge it either, requests
+            // are redeemed at the settled nav
+            let lp = 2_500;
+            let before = nav.lamports_for_withdraw(lp, supply).unwrap();
+            let redeemed = settled.lamports_for_withdraw(lp, supply).unwrap();
+            match outcome {
+                Outcome::Won => assert!(redeemed > before),
+                Outcome::Lost => assert!(redeemed < before),
+                Outcome::Tied => assert_eq!(redeemed, before),
+            }
+        }
+    }
+}
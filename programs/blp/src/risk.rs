@@ -0,0 +1,119 @@
+//! Limits on what the bot can do with the pool's funds, checked every time
+//! it joins a game.
+
+use anchor_lang::prelude::*;
+
+use crate::nav::Nav;
+use crate::BlpError;
+
+pub const MAX_BPS: u64 = 10_000;
+pub const MAX_ALLOWED_MINTS: usize = 8;
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, AnchorSerialize, AnchorDeserialize)]
+pub struct RiskParams {
+    // largest wager the bot can match in a single game
+    pub max_wager: u64,
+    // share of the nav that can be in open games at once, new game included
+    pub max_in_games_bps: u64,
+    pub max_open_games: u64,
+    // mints the bot can play for, sol games count as the native mint
+    pub allowed_mints: Vec<Pubkey>,
+}
+
+impl RiskParams {
+    pub fn is_valid(&self) -> bool {
+        self.max_in_games_bps <= MAX_BPS && self.allowed_mints.len() <= MAX_ALLOWED_MINTS
+    }
+
+    /// Whether the bot can join another game of `wager` in `mint` with the
+    /// pool at `nav` and `open_games` already running.
+    pub fn check(
+        &self,
+        nav: Nav,
+        open_games: u64,
+        mint: Pubkey,
+        wager: u64,
+        best_of: u8,
+    ) -> std::result::Result<(), BlpError> {
+        if !self.allowed_mints.contains(&mint) {
+            return Err(BlpError::MintNotAllowed);
+        }
+        // nothing plays the later rounds of a match for the pool, player 1
+        // would win it on expiry every time
+        if best_of != 1 {
+            return Err(BlpError::MatchNotSupported);
+        }
+        if wager > self.max_wager {
+            return Err(BlpError::WagerTooLarge);
+        }
+        if open_games >= self.max_open_games {
+            return Err(BlpError::TooManyOpenGames);
+        }
+        // joining moves the wager from liquid to in games, the nav stays put
+        let in_games = nav.in_games as u128 + wager as u128;
+        if in_games * MAX_BPS as u128 > nav.total() * self.max_in_games_bps as u128 {
+            return Err(BlpError::TooMuchInGames);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_check() {
+        let sol = Pubkey::new_unique();
+        let params = RiskParams {
+            max_wager: 1_000,
+            max_in_games_bps: 2_500,
+            max_open_games: 2,
+            allowed_mints: vec![sol],
+        };
+        assert!(params.is_valid());
+        let nav = Nav {
+            liquid: 9_000,
+            in_games: 1_000,
+        };
+
+        assert!(params.check(nav, 1, sol, 1_000, 1).is_ok());
+        assert!(matches!(
+            params.check(nav, 1, Pubkey::new_unique(), 1_000, 1),
+            Err(BlpError::MintNotAllowed)
+        ));
+        assert!(matches!(
+            params.check(nav, 1, sol, 1_001, 1),
+            Err(BlpError::WagerTooLarge)
+        ));
+        assert!(matches!(
+            params.check(nav, 1, sol, 1_000, 3),
+            Err(BlpError::MatchNotSupported)
+        ));
+        assert!(matches!(
+            params.check(nav, 2, sol, 1, 1),
+            Err(BlpError::TooManyOpenGames)
+        ));
+        // 2_000 of 10_000 in games already, 500 more is the most allowed
+        let nav = Nav {
+            liquid: 8_000,
+            in_games: 2_000,
+        };
+        assert!(params.check(nav, 1, sol, 500, 1).is_ok());
+        assert!(matches!(
+            params.check(nav, 1, sol, 501, 1),
+            Err(BlpError::TooMuchInGames)
+        ));
+
+        // nothing is allowed until the limits are set
+        assert!(matches!(
+            RiskParams::default().check(nav, 0, sol, 0, 1),
+            Err(BlpError::MintNotAllowed)
+        ));
+        assert!(!RiskParams {
+            max_in_games_bps: MAX_BPS + 1,
+            ..params
+        }
+        .is_valid());
+    }
+}
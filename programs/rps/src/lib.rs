@@ -1,10 +1,15 @@
 #![allow(clippy::result_large_err)]
 use anchor_lang::prelude::*;
-pub mod logic;
+pub use rps_core::logic;
 
-use logic::{process_action, Actions, GameConfig, GameState, Winner, RPS};
+use logic::{
+    Actions, CommitmentVersion, GameConfig, GameState, Salt, Score, TransitionError, Winner, RPS,
+};
 use program::Rps;
+use rps_core::escrow::{Escrow, SolEscrow};
+use rps_core::state::{GameData, PlayerStats};
 use serde::{Deserialize, Serialize};
+use std::ops::{Deref, DerefMut};
 
 declare_id!("rpsVN2ZC1K9hoGPs83xahjWo46cDNP49Tk7rQb56ipE");
 
@@ -15,7 +20,7 @@ pub mod local_bpf_loader {
     solana_program::declare_id!("BPFLoader2111111111111111111111111111111111");
 }
 
-static PLAYER_1_FEE_BPS: u64 = 350;
+const MAX_BPS: u64 = 10000;
 
 #[program]
 pub mod rps {
@@ -28,70 +33,87 @@ pub mod rps {
         Ok(())
     }
 
+    // whoever initializes the config picks the admin, so only the upgrade
+    // authority can, nobody can front-run it right after a deploy
+    pub fn initialize_config(ctx: Context<InitializeConfig>, params: ConfigParams) -> Result<()> {
+        ctx.accounts.config.set(params)
+    }
+
+    pub fn update_config(ctx: Context<UpdateConfig>, params: ConfigParams) -> Result<()> {
+        ctx.accounts.config.set(params)
+    }
+
+    // only stops new games and joins, funds already in games can always get out
+    pub fn set_paused(ctx: Context<SetPaused>, paused: bool) -> Result<()> {
+        ctx.accounts.config.paused = paused;
+
+        Ok(())
+    }
+
     pub fn create_game(
         ctx: Context<CreateGame>,
         game_seed: u64,
         commitment: [u8; 32],
         wager_amount: u64,
-        entry_proof: Option<[u8; 32]>,
+        config: GameConfig,
     ) -> Result<()> {
+        let slot = Clock::get()?.slot;
         ctx.accounts.game.state = GameState::Initialized;
 
         let action = Actions::CreateGame {
             player_1_pubkey: ctx.accounts.player.key(),
             commitment,
-            config: GameConfig { entry_proof },
+            config,
         };
 
         ctx.accounts.game.seed = game_seed;
         ctx.accounts.game.wager_amount = wager_amount;
+        if wager_amount < ctx.accounts.config.min_wager
+            || wager_amount > ctx.accounts.config.max_wager
+        {
+            return err!(RpsError::WagerOutOfBounds);
+        }
+        if config.commitment_version == CommitmentVersion::V1
+            && !ctx.accounts.config.allow_v1_commitments
+        {
+            return err!(RpsError::V1CommitmentsDisabled);
+        }
         ctx.accounts.game.fee_amount = wager_amount
-            .checked_mul(PLAYER_1_FEE_BPS)
+            .checked_mul(ctx.accounts.config.fee_bps)
             .ok_or(RpsError::BetTooLarge)?
-            .checked_div(10000)
+            .checked_div(MAX_BPS)
             .ok_or(RpsError::BetTooLarge)?;
         ctx.accounts.game.state = process_action(
             ctx.accounts.game.key(),
             ctx.accounts.game.state,
             action,
-            Clock::get()?.slot,
-        );
+            slot,
+        )?;
 
         match ctx.accounts.game.state {
             GameState::AcceptingChallenge { .. } => {
-                anchor_lang::system_program::transfer(
-                    CpiContext::new(
-                        ctx.accounts.system_program.to_account_info(),
-                        anchor_lang::system_program::Transfer {
-                            from: ctx.accounts.player.to_account_info(),
-                            to: ctx.accounts.game_authority.to_account_info(),
-                        },
-                    ),
+                ctx.accounts.escrow().deposit(
+                    ctx.accounts.player.to_account_info(),
+                    ctx.accounts.player.to_account_info(),
                     wager_amount + ctx.accounts.game.fee_amount,
                 )?;
             }
             _ => panic!("Invalid state"),
         };
 
-        ctx.accounts.player_info.amount_in_games = ctx
-            .accounts
-            .player_info
-            .amount_in_games
-            .checked_add(ctx.accounts.game.wager_amount)
-            .ok_or(RpsError::BetTooLarge)?;
-
-        ctx.accounts.player_info.lifetime_wagering = ctx
-            .accounts
+        ctx.accounts
             .player_info
-            .lifetime_wagering
-            .checked_add(ctx.accounts.game.wager_amount)
+            .enter_game(wager_amount)
             .ok_or(RpsError::BetTooLarge)?;
 
         emit!(GameStartEvent {
             game_pubkey: ctx.accounts.game.key(),
+            seed: game_seed,
+            player_1: ctx.accounts.player.key(),
             wager_amount,
             fee_amount: ctx.accounts.game.fee_amount,
-            public: entry_proof.is_some(),
+            public: config.entry_proof.is_none(),
+            slot,
         });
         Ok(())
     }
@@ -102,65 +124,138 @@ pub mod rps {
             choice,
             secret,
         };
+        join_game_with_action(ctx, action)
+    }
+
+    pub fn join_game_committed(
+        ctx: Context<JoinGame>,
+        commitment: [u8; 32],
+        secret: Option<u64>,
+    ) -> Result<()> {
+        let action = Actions::JoinGameCommitted {
+            player_2_pubkey: ctx.accounts.player.key(),
+            commitment,
+            secret,
+        };
+        join_game_with_action(ctx, action)
+    }
+
+    pub fn reveal_game(ctx: Context<RevealGame>, choice: RPS, salt: Salt) -> Result<()> {
+        let slot = Clock::get()?.slot;
+        let action = Actions::Reveal {
+            player_pubkey: ctx.accounts.player.key(),
+            choice,
+            salt,
+        };
 
         ctx.accounts.game.state = process_action(
             ctx.accounts.game.key(),
             ctx.accounts.game.state,
             action,
-            Clock::get()?.slot,
-        );
+            slot,
+        )?;
 
-        match ctx.accounts.game.state {
-            GameState::AcceptingReveal { .. } => {
-                // transfer in the wager
-                anchor_lang::system_program::transfer(
-                    CpiContext::new(
-                        ctx.accounts.system_program.to_account_info(),
-                        anchor_lang::system_program::Transfer {
-                            from: ctx.accounts.player.to_account_info(),
-                            to: ctx.accounts.game_authority.to_account_info(),
-                        },
-                    ),
-                    ctx.accounts.game.wager_amount,
-                )?;
-            }
-            _ => panic!("Invalid state"),
+        let game = &ctx.accounts.game;
+        let (choice_1, choice_2) = game.choices();
+        emit!(GameRevealEvent {
+            game_pubkey: game.key(),
+            seed: game.seed,
+            player: ctx.accounts.player.key(),
+            player_1: game.player_1().unwrap_or_default(),
+            player_2: game.player_2().unwrap_or_default(),
+            choice_1,
+            choice_2,
+            score: game.score(),
+            result: game.result(),
+            slot,
+        });
+
+        Ok(())
+    }
+
+    pub fn commit_round(ctx: Context<CommitRound>, commitment: [u8; 32]) -> Result<()> {
+        let slot = Clock::get()?.slot;
+        let action = Actions::CommitRound {
+            player_1_pubkey: ctx.accounts.player.key(),
+            commitment,
         };
 
-        ctx.accounts.player_info.amount_in_games = ctx
-            .accounts
-            .player_info
-            .amount_in_games
-            .checked_add(ctx.accounts.game.wager_amount)
-            .ok_or(RpsError::BetTooLarge)?;
-        ctx.accounts.player_info.lifetime_wagering = ctx
-            .accounts
-            .player_info
-            .lifetime_wagering
-            .checked_add(ctx.accounts.game.wager_amount)
-            .ok_or(RpsError::BetTooLarge)?;
+        ctx.accounts.game.state = process_action(
+            ctx.accounts.game.key(),
+            ctx.accounts.game.state,
+            action,
+            slot,
+        )?;
+
+        let game = &ctx.accounts.game;
+        emit!(GameRoundEvent {
+            game_pubkey: game.key(),
+            seed: game.seed,
+            player: ctx.accounts.player.key(),
+            choice: None,
+            score: game.score(),
+            slot,
+        });
 
         Ok(())
     }
 
-    pub fn reveal_game(ctx: Context<RevealGame>, choice: RPS, salt: u64) -> Result<()> {
-        let action = Actions::Reveal {
-            player_1_pubkey: ctx.accounts.player.key(),
+    pub fn play_round(ctx: Context<PlayRound>, choice: RPS) -> Result<()> {
+        let slot = Clock::get()?.slot;
+        let action = Actions::PlayRound {
+            player_2_pubkey: ctx.accounts.player.key(),
             choice,
-            salt,
         };
 
         ctx.accounts.game.state = process_action(
             ctx.accounts.game.key(),
             ctx.accounts.game.state,
             action,
-            Clock::get()?.slot,
-        );
+            slot,
+        )?;
+
+        let game = &ctx.accounts.game;
+        emit!(GameRoundEvent {
+            game_pubkey: game.key(),
+            seed: game.seed,
+            player: ctx.accounts.player.key(),
+            choice: Some(choice),
+            score: game.score(),
+            slot,
+        });
+
+        Ok(())
+    }
+
+    pub fn play_round_committed(ctx: Context<PlayRound>, commitment: [u8; 32]) -> Result<()> {
+        let slot = Clock::get()?.slot;
+        let action = Actions::PlayRoundCommitted {
+            player_2_pubkey: ctx.accounts.player.key(),
+            commitment,
+        };
+
+        ctx.accounts.game.state = process_action(
+            ctx.accounts.game.key(),
+            ctx.accounts.game.state,
+            action,
+            slot,
+        )?;
+
+        let game = &ctx.accounts.game;
+        emit!(GameRoundEvent {
+            game_pubkey: game.key(),
+            seed: game.seed,
+            player: ctx.accounts.player.key(),
+            choice: None,
+            score: game.score(),
+            slot,
+        });
 
         Ok(())
     }
 
     pub fn expire_game(ctx: Context<ExpireGame>) -> Result<()> {
+        let slot = Clock::get()?.slot;
         let action = Actions::ExpireGame {
             player_pubkey: ctx.accounts.player.key(),
         };
@@ -168,238 +263,247 @@ pub mod rps {
             ctx.accounts.game.key(),
             ctx.accounts.game.state,
             action,
-            Clock::get()?.slot,
-        );
+            slot,
+        )?;
+        let game = &ctx.accounts.game;
+        let (choice_1, choice_2) = game.choices();
+        emit!(GameExpireEvent {
+            game_pubkey: game.key(),
+            seed: game.seed,
+            expired_by: ctx.accounts.player.key(),
+            player_1: game.player_1().unwrap_or_default(),
+            player_2: game.player_2().unwrap_or_default(),
+            choice_1,
+            choice_2,
+            result: game.result(),
+            slot,
+        });
+
         Ok(())
     }
 
-    pub fn settle_game(ctx: Context<SettleGame>) -> Result<()> {
-        let action = Actions::Settle;
+    pub fn cancel_game(ctx: Context<CancelGame>) -> Result<()> {
+        let slot = Clock::get()?.slot;
+        let action = Actions::CancelGame {
+            player_pubkey: ctx.accounts.player.key(),
+        };
         ctx.accounts.game.state = process_action(
             ctx.accounts.game.key(),
             ctx.accounts.game.state,
             action,
-            Clock::get()?.slot,
-        );
+            slot,
+        )?;
 
-        let is_p1_expired = ctx.accounts.player_1.key() == ctx.accounts.player_2.key();
+        // nobody joined so player 1 gets the fee back too
+        ctx.accounts.escrow().withdraw(
+            ctx.accounts.player.to_account_info(),
+            ctx.accounts.game.wager_amount + ctx.accounts.game.fee_amount,
+            &[&[
+                b"authority".as_ref(),
+                ctx.accounts.game.key().as_ref(),
+                &[*ctx.bumps.get("game_authority").unwrap()],
+            ]],
+        )?;
 
-        ctx.accounts.player_1_info.amount_in_games = ctx
-            .accounts
-            .player_1_info
-            .amount_in_games
-            .checked_sub(ctx.accounts.game.wager_amount)
+        let wager_amount = ctx.accounts.game.wager_amount;
+        ctx.accounts
+            .player_info
+            .cancel_game(wager_amount)
             .ok_or(RpsError::MathOverflow)?;
 
-        if !is_p1_expired {
-            ctx.accounts.player_2_info.amount_in_games = ctx
-                .accounts
-                .player_2_info
-                .amount_in_games
-                .checked_sub(ctx.accounts.game.wager_amount)
-                .ok_or(RpsError::MathOverflow)?;
-        }
+        emit_game_closed(ctx.accounts.game.key(), &ctx.accounts.game, slot);
 
-        match ctx.accounts.game.state {
-            GameState::Settled {
-                result,
-                player_1,
-                player_2,
-                config: _,
-            } => match result {
-                Winner::P1 => {
-                    // if game expired they just get wager_amount back
-                    let payout_amount = if player_1.pubkey() == player_2.pubkey() {
-                        ctx.accounts.game.wager_amount
-                    } else {
-                        ctx.accounts.game.wager_amount * 2
-                    };
-                    anchor_lang::system_program::transfer(
-                        CpiContext::new_with_signer(
-                            ctx.accounts.system_program.to_account_info(),
-                            anchor_lang::system_program::Transfer {
-                                from: ctx.accounts.game_authority.to_account_info(),
-                                to: ctx.accounts.player_1.to_account_info(),
-                            },
-                            &[&[
-                                b"authority".as_ref(),
-                                ctx.accounts.game.key().as_ref(),
-                                &[*ctx.bumps.get("game_authority").unwrap()],
-                            ]],
-                        ),
-                        payout_amount,
-                    )?;
-
-                    if !is_p1_expired {
-                        ctx.accounts.player_1_info.games_won = ctx
-                            .accounts
-                            .player_1_info
-                            .games_won
-                            .checked_add(1)
-                            .ok_or(RpsError::MathOverflow)?;
-                        ctx.accounts.player_1_info.lifetime_earnings = ctx
-                            .accounts
-                            .player_1_info
-                            .lifetime_earnings
-                            .checked_add(ctx.accounts.game.wager_amount.try_into().unwrap())
-                            .ok_or(RpsError::MathOverflow)?;
-
-                        ctx.accounts.player_2_info.games_lost = ctx
-                            .accounts
-                            .player_2_info
-                            .games_lost
-                            .checked_add(1)
-                            .ok_or(RpsError::MathOverflow)?;
-                        ctx.accounts.player_2_info.lifetime_earnings = ctx
-                            .accounts
-                            .player_2_info
-                            .lifetime_earnings
-                            .checked_sub(ctx.accounts.game.wager_amount.try_into().unwrap())
-                            .ok_or(RpsError::MathOverflow)?;
-                    }
-                }
-                Winner::P2 => {
-                    anchor_lang::system_program::transfer(
-                        CpiContext::new_with_signer(
-                            ctx.accounts.system_program.to_account_info(),
-                            anchor_lang::system_program::Transfer {
-                                from: ctx.accounts.game_authority.to_account_info(),
-                                to: ctx.accounts.player_2.to_account_info(),
-                            },
-                            &[&[
-                                b"authority".as_ref(),
-                                ctx.accounts.game.key().as_ref(),
-                                &[*ctx.bumps.get("game_authority").unwrap()],
-                            ]],
-                        ),
-                        ctx.accounts.game.wager_amount * 2,
-                    )?;
-
-                    ctx.accounts.player_1_info.games_lost = ctx
-                        .accounts
-                        .player_1_info
-                        .games_lost
-                        .checked_add(1)
-                        .ok_or(RpsError::MathOverflow)?;
-                    ctx.accounts.player_1_info.lifetime_earnings = ctx
-                        .accounts
-                        .player_1_info
-                        .lifetime_earnings
-                        .checked_sub(ctx.accounts.game.wager_amount.try_into().unwrap())
-                        .ok_or(RpsError::MathOverflow)?;
-
-                    ctx.accounts.player_2_info.games_won = ctx
-                        .accounts
-                        .player_2_info
-                        .games_won
-                        .checked_add(1)
-                        .ok_or(RpsError::MathOverflow)?;
-                    ctx.accounts.player_2_info.lifetime_earnings = ctx
-                        .accounts
-                        .player_2_info
-                        .lifetime_earnings
-                        .checked_add(ctx.accounts.game.wager_amount.try_into().unwrap())
-                        .ok_or(RpsError::MathOverflow)?;
-                }
-                Winner::TIE => {
-                    anchor_lang::system_program::transfer(
-                        CpiContext::new_with_signer(
-                            ctx.accounts.system_program.to_account_info(),
-                            anchor_lang::system_program::Transfer {
-                                from: ctx.accounts.game_authority.to_account_info(),
-                                to: ctx.accounts.player_1.to_account_info(),
-                            },
-                            &[&[
-                                b"authority".as_ref(),
-                                ctx.accounts.game.key().as_ref(),
-                                &[*ctx.bumps.get("game_authority").unwrap()],
-                            ]],
-                        ),
-                        ctx.accounts.game.wager_amount,
-                    )?;
-                    anchor_lang::system_program::transfer(
-                        CpiContext::new_with_signer(
-                            ctx.accounts.system_program.to_account_info(),
-                            anchor_lang::system_program::Transfer {
-                                from: ctx.accounts.game_authority.to_account_info(),
-                                to: ctx.accounts.player_2.to_account_info(),
-                            },
-                            &[&[
-                                b"authority".as_ref(),
-                                ctx.accounts.game.key().as_ref(),
-                                &[*ctx.bumps.get("game_authority").unwrap()],
-                            ]],
-                        ),
-                        ctx.accounts.game.wager_amount,
-                    )?;
-                    ctx.accounts.player_1_info.games_drawn = ctx
-                        .accounts
-                        .player_1_info
-                        .games_drawn
-                        .checked_add(1)
-                        .ok_or(RpsError::MathOverflow)?;
-
-                    ctx.accounts.player_2_info.games_drawn = ctx
-                        .accounts
-                        .player_2_info
-                        .games_drawn
-                        .checked_add(1)
-                        .ok_or(RpsError::MathOverflow)?;
-                }
-            },
-            _ => panic!("Invalid state"),
-        };
+        Ok(())
+    }
 
-        // transfer out the fee
-        anchor_lang::system_program::transfer(
-            CpiContext::new_with_signer(
-                ctx.accounts.system_program.to_account_info(),
-                anchor_lang::system_program::Transfer {
-                    from: ctx.accounts.game_authority.to_account_info(),
-                    to: ctx.accounts.player_2.to_account_info(),
-                },
-                &[&[
-                    b"authority".as_ref(),
-                    ctx.accounts.game.key().as_ref(),
-                    &[*ctx.bumps.get("game_authority").unwrap()],
-                ]],
-            ),
-            ctx.accounts.game.fee_amount,
-        )?;
+    pub fn settle_game(ctx: Context<SettleGame>) -> Result<()> {
+        settle(ctx.accounts, *ctx.bumps.get("game_authority").unwrap())
+    }
 
-        Ok(())
+    // settles, logs the result and closes the game in one go. there's no
+    // signer so anyone can crank finished games
+    pub fn settle_and_close(ctx: Context<SettleGame>) -> Result<()> {
+        settle(ctx.accounts, *ctx.bumps.get("game_authority").unwrap())?;
+        log_game_result(&ctx.accounts.game);
+        emit_game_closed(
+            ctx.accounts.game.key(),
+            &ctx.accounts.game,
+            Clock::get()?.slot,
+        );
+
+        ctx.accounts
+            .game
+            .close(ctx.accounts.player_1.to_account_info())
     }
 
     pub fn clean_game(ctx: Context<CleanGame>) -> Result<()> {
-        match ctx.accounts.game.state {
-            GameState::Settled {
+        log_game_result(&ctx.accounts.game);
+        emit_game_closed(
+            ctx.accounts.game.key(),
+            &ctx.accounts.game,
+            Clock::get()?.slot,
+        );
+        Ok(())
+    }
+}
+
+// rps_core doesn't know our error codes so every transition maps its errors here
+fn process_action(
+    state_pubkey: Pubkey,
+    state: GameState,
+    action: Actions,
+    slot: u64,
+) -> Result<GameState> {
+    logic::process_action(crate::ID, state_pubkey, state, action, slot)
+        .map_err(|e| RpsError::from(e).into())
+}
+
+fn settle(accounts: &mut SettleGame, game_authority_bump: u8) -> Result<()> {
+    let slot = Clock::get()?.slot;
+    let action = Actions::Settle;
+    accounts.game.state = process_action(accounts.game.key(), accounts.game.state, action, slot)?;
+
+    let game: GameData = **accounts.game;
+    game.record_settlement(&mut accounts.player_1_info, &mut accounts.player_2_info)
+        .ok_or(RpsError::MathOverflow)?;
+
+    let treasury_fee_amount = accounts
+        .config
+        .treasury_fee(game.fee_amount)
+        .ok_or(RpsError::MathOverflow)?;
+    let payouts = game
+        .payouts(treasury_fee_amount)
+        .ok_or(RpsError::MathOverflow)?;
+
+    let game_key = accounts.game.key();
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        b"authority".as_ref(),
+        game_key.as_ref(),
+        &[game_authority_bump],
+    ]];
+    let escrow = accounts.escrow();
+    escrow.withdraw(
+        accounts.player_1.to_account_info(),
+        payouts.player_1,
+        signer_seeds,
+    )?;
+    escrow.withdraw(
+        accounts.player_2.to_account_info(),
+        payouts.player_2,
+        signer_seeds,
+    )?;
+    escrow.withdraw(
+        accounts.treasury.to_account_info(),
+        payouts.treasury,
+        signer_seeds,
+    )?;
+
+    let (choice_1, choice_2) = game.choices();
+    emit!(GameSettleEvent {
+        game_pubkey: game_key,
+        seed: game.seed,
+        player_1: game.player_1().unwrap_or_default(),
+        player_2: game.player_2().unwrap_or_default(),
+        choice_1,
+        choice_2,
+        score: game.score(),
+        result: game.result(),
+        wager_amount: game.wager_amount,
+        fee_amount: game.fee_amount,
+        treasury_fee_amount: payouts.treasury,
+        slot,
+    });
+
+    Ok(())
+}
+
+fn log_game_result(game: &Game) {
+    match game.state {
+        GameState::Settled {
+            result,
+            player_1,
+            player_2,
+            config: GameConfig { entry_proof, .. },
+            ..
+        } => {
+            let gr = ReadableGameEvent {
+                event_name: "game_result".to_string(),
+                event_version: 1,
+                player_1: player_1.pubkey().to_string(),
+                choice_1: player_1.choice_or_unrevealed(),
+                player_2: player_2.pubkey().to_string(),
+                choice_2: player_2.choice_or_unrevealed(),
                 result,
-                player_1,
-                player_2,
-                config: GameConfig { entry_proof },
-            } => {
-                let gr = ReadableGameEvent {
-                    event_name: "game_result".to_string(),
-                    event_version: 1,
-                    player_1: player_1.pubkey().to_string(),
-                    choice_1: player_1.choice_or_unrevealed(),
-                    player_2: player_2.pubkey().to_string(),
-                    choice_2: player_2.choice_or_unrevealed(),
-                    result,
-                    wager_amount: ctx.accounts.game.wager_amount,
-                    fee_amount: ctx.accounts.game.fee_amount,
-                    public: entry_proof.is_none(),
-                };
-                msg!("{}", serde_json::to_string(&gr).unwrap());
-            }
-            _ => {
-                panic!("game not settled can't clean")
-            }
+                wager_amount: game.wager_amount,
+                fee_amount: game.fee_amount,
+                public: entry_proof.is_none(),
+            };
+            msg!("{}", serde_json::to_string(&gr).unwrap());
+        }
+        _ => {
+            panic!("game not settled can't clean")
         }
-        Ok(())
     }
 }
 
+fn emit_game_closed(game_pubkey: Pubkey, game: &Game, slot: u64) {
+    emit!(GameCloseEvent {
+        game_pubkey,
+        seed: game.seed,
+        player_1: game.player_1().unwrap_or_default(),
+        player_2: game.player_2(),
+        result: game.result(),
+        wager_amount: game.wager_amount,
+        fee_amount: game.fee_amount,
+        slot,
+    });
+}
+
+// shared by join_game and join_game_committed, they only differ in whether
+// player 2 plays their choice or a commitment
+fn join_game_with_action(ctx: Context<JoinGame>, action: Actions) -> Result<()> {
+    let slot = Clock::get()?.slot;
+    ctx.accounts.game.state = process_action(
+        ctx.accounts.game.key(),
+        ctx.accounts.game.state,
+        action,
+        slot,
+    )?;
+
+    match ctx.accounts.game.state {
+        GameState::AcceptingReveal { .. } => {
+            // transfer in the wager
+            ctx.accounts.escrow().deposit(
+                ctx.accounts.player.to_account_info(),
+                ctx.accounts.player.to_account_info(),
+                ctx.accounts.game.wager_amount,
+            )?;
+        }
+        _ => panic!("Invalid state"),
+    };
+
+    let wager_amount = ctx.accounts.game.wager_amount;
+    ctx.accounts
+        .player_info
+        .enter_game(wager_amount)
+        .ok_or(RpsError::BetTooLarge)?;
+
+    let game = &ctx.accounts.game;
+    let (_, choice_2) = game.choices();
+    emit!(GameJoinEvent {
+        game_pubkey: game.key(),
+        seed: game.seed,
+        player_1: game.player_1().unwrap_or_default(),
+        player_2: ctx.accounts.player.key(),
+        choice_2,
+        wager_amount: game.wager_amount,
+        fee_amount: game.fee_amount,
+        slot,
+    });
+
+    Ok(())
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ReadableGameEvent {
     event_name: String,
@@ -417,9 +521,151 @@ pub struct ReadableGameEvent {
 #[event]
 pub struct GameStartEvent {
     game_pubkey: Pubkey,
+    seed: u64,
+    player_1: Pubkey,
     wager_amount: u64,
     fee_amount: u64,
     public: bool,
+    slot: u64,
+}
+
+// choices stay None until revealed, result stays None until decided
+#[event]
+pub struct GameJoinEvent {
+    game_pubkey: Pubkey,
+    seed: u64,
+    player_1: Pubkey,
+    player_2: Pubkey,
+    choice_2: Option<RPS>,
+    wager_amount: u64,
+    fee_amount: u64,
+    slot: u64,
+}
+
+#[event]
+pub struct GameRevealEvent {
+    game_pubkey: Pubkey,
+    seed: u64,
+    player: Pubkey,
+    player_1: Pubkey,
+    player_2: Pubkey,
+    choice_1: Option<RPS>,
+    choice_2: Option<RPS>,
+    score: Score,
+    result: Option<Winner>,
+    slot: u64,
+}
+
+// commit_round, play_round and play_round_committed
+#[event]
+pub struct GameRoundEvent {
+    game_pubkey: Pubkey,
+    seed: u64,
+    player: Pubkey,
+    choice: Option<RPS>,
+    score: Score,
+    slot: u64,
+}
+
+#[event]
+pub struct GameExpireEvent {
+    game_pubkey: Pubkey,
+    seed: u64,
+    expired_by: Pubkey,
+    player_1: Pubkey,
+    player_2: Pubkey,
+    choice_1: Option<RPS>,
+    choice_2: Option<RPS>,
+    result: Option<Winner>,
+    slot: u64,
+}
+
+#[event]
+pub struct GameSettleEvent {
+    game_pubkey: Pubkey,
+    seed: u64,
+    player_1: Pubkey,
+    player_2: Pubkey,
+    choice_1: Option<RPS>,
+    choice_2: Option<RPS>,
+    score: Score,
+    result: Option<Winner>,
+    wager_amount: u64,
+    fee_amount: u64,
+    treasury_fee_amount: u64,
+    slot: u64,
+}
+
+// emitted by cancel_game, settle_and_close and clean_game
+#[event]
+pub struct GameCloseEvent {
+    game_pubkey: Pubkey,
+    seed: u64,
+    player_1: Pubkey,
+    player_2: Option<Pubkey>,
+    result: Option<Winner>,
+    wager_amount: u64,
+    fee_amount: u64,
+    slot: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ConfigParams {
+    pub admin: Pubkey,
+    pub guardian: Pubkey,
+    pub treasury: Pubkey,
+    pub fee_bps: u64,
+    pub treasury_fee_bps: u64,
+    pub min_wager: u64,
+    pub max_wager: u64,
+    pub allow_v1_commitments: bool,
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        seeds = [b"config".as_ref()],
+        bump,
+        payer = payer,
+        space = Config::space()
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, Rps>,
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(payer.key())
+            @ RpsError::NotUpgradeAuthority
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"config".as_ref()],
+        bump,
+        has_one = admin,
+    )]
+    pub config: Account<'info, Config>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetPaused<'info> {
+    #[account(mut, seeds = [b"config".as_ref()], bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(constraint = config.can_pause(authority.key()) @ RpsError::NotPauseAuthority)]
+    pub authority: Signer<'info>,
 }
 
 #[derive(Accounts)]
@@ -451,6 +697,9 @@ pub struct CreateGame<'info> {
     )]
     pub game: Account<'info, Game>,
 
+    #[account(seeds = [b"config".as_ref()], bump, constraint = !config.paused @ RpsError::Paused)]
+    pub config: Account<'info, Config>,
+
     #[account(mut)]
     pub player: Signer<'info>,
 
@@ -469,6 +718,15 @@ pub struct CreateGame<'info> {
     pub system_program: Program<'info, System>,
 }
 
+impl<'info> CreateGame<'info> {
+    fn escrow(&self) -> SolEscrow<'info> {
+        SolEscrow {
+            game_authority: self.game_authority.to_account_info(),
+            system_program: self.system_program.to_account_info(),
+        }
+    }
+}
+
 #[derive(Accounts)]
 pub struct JoinGame<'info> {
     #[account(mut)]
@@ -489,6 +747,9 @@ pub struct JoinGame<'info> {
     )]
     pub game: Account<'info, Game>,
 
+    #[account(seeds = [b"config".as_ref()], bump, constraint = !config.paused @ RpsError::Paused)]
+    pub config: Account<'info, Config>,
+
     /// CHECK: this is a pda that escrows the sol
     #[account(mut, seeds = [b"authority".as_ref(), game.key().as_ref()], bump)]
     pub game_authority: AccountInfo<'info>,
@@ -496,6 +757,15 @@ pub struct JoinGame<'info> {
     pub system_program: Program<'info, System>,
 }
 
+impl<'info> JoinGame<'info> {
+    fn escrow(&self) -> SolEscrow<'info> {
+        SolEscrow {
+            game_authority: self.game_authority.to_account_info(),
+            system_program: self.system_program.to_account_info(),
+        }
+    }
+}
+
 #[derive(Accounts)]
 pub struct RevealGame<'info> {
     #[account(
@@ -517,6 +787,32 @@ pub struct RevealGame<'info> {
     pub player_info: Account<'info, PlayerInfo>,
 }
 
+#[derive(Accounts)]
+pub struct CommitRound<'info> {
+    #[account(
+        mut,
+        seeds = [b"game".as_ref(), &game.seed.to_le_bytes()],
+        bump,
+    )]
+    pub game: Account<'info, Game>,
+
+    #[account(constraint = Some(player.key()) == game.player_1())]
+    pub player: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct PlayRound<'info> {
+    #[account(
+        mut,
+        seeds = [b"game".as_ref(), &game.seed.to_le_bytes()],
+        bump,
+    )]
+    pub game: Account<'info, Game>,
+
+    #[account(constraint = Some(player.key()) == game.player_2())]
+    pub player: Signer<'info>,
+}
+
 #[derive(Accounts)]
 pub struct ExpireGame<'info> {
     #[account(
@@ -540,6 +836,43 @@ pub struct ExpireGame<'info> {
     pub player_info: Account<'info, PlayerInfo>,
 }
 
+#[derive(Accounts)]
+pub struct CancelGame<'info> {
+    #[account(
+        mut,
+        close = player,
+        seeds = [b"game".as_ref(), &game.seed.to_le_bytes()],
+        bump,
+    )]
+    pub game: Account<'info, Game>,
+
+    #[account(mut, constraint = Some(player.key()) == game.player_1())]
+    pub player: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"player_info".as_ref(), player.key().as_ref()],
+        bump,
+        constraint = player_info.owner == player.key()
+    )]
+    pub player_info: Account<'info, PlayerInfo>,
+
+    /// CHECK: this is a pda that manages the escrow account
+    #[account(mut, seeds = [b"authority".as_ref(), game.key().as_ref()], bump)]
+    pub game_authority: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CancelGame<'info> {
+    fn escrow(&self) -> SolEscrow<'info> {
+        SolEscrow {
+            game_authority: self.game_authority.to_account_info(),
+            system_program: self.system_program.to_account_info(),
+        }
+    }
+}
+
 #[derive(Accounts)]
 pub struct SettleGame<'info> {
     #[account(
@@ -575,9 +908,25 @@ pub struct SettleGame<'info> {
     #[account(mut, seeds = [b"authority".as_ref(), game.key().as_ref()], bump)]
     pub game_authority: AccountInfo<'info>,
 
+    #[account(seeds = [b"config".as_ref()], bump)]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: only receives lamports, checked against the config
+    #[account(mut, constraint = treasury.key() == config.treasury)]
+    pub treasury: AccountInfo<'info>,
+
     pub system_program: Program<'info, System>,
 }
 
+impl<'info> SettleGame<'info> {
+    fn escrow(&self) -> SolEscrow<'info> {
+        SolEscrow {
+            game_authority: self.game_authority.to_account_info(),
+            system_program: self.system_program.to_account_info(),
+        }
+    }
+}
+
 #[derive(Accounts)]
 pub struct CleanGame<'info> {
     #[account(
@@ -609,47 +958,34 @@ pub struct CleanGame<'info> {
 #[account]
 #[derive(Debug, PartialEq, Eq, Copy)]
 pub struct Game {
-    pub seed: u64,
-    pub wager_amount: u64,
-    pub fee_amount: u64,
-    pub state: GameState,
+    pub data: GameData,
 }
 
 impl Game {
     pub fn space() -> usize {
-        // idk lmao
-        192
-    }
-    pub fn player_1(self) -> Option<Pubkey> {
-        match self.state {
-            GameState::AcceptingChallenge { player_1, .. } => Some(player_1.pubkey()),
-            GameState::AcceptingReveal { player_1, .. } => Some(player_1.pubkey()),
-            GameState::AcceptingSettle { player_1, .. } => Some(player_1.pubkey()),
-            GameState::Settled { player_1, .. } => Some(player_1.pubkey()),
-            _ => None,
-        }
+        // idk lmao leaving some space for expansion
+        320
     }
-    pub fn player_2(self) -> Option<Pubkey> {
-        match self.state {
-            GameState::AcceptingReveal { player_2, .. } => Some(player_2.pubkey()),
-            GameState::AcceptingSettle { player_2, .. } => Some(player_2.pubkey()),
-            GameState::Settled { player_2, .. } => Some(player_2.pubkey()),
-            _ => None,
-        }
+}
+
+impl Deref for Game {
+    type Target = GameData;
+
+    fn deref(&self) -> &GameData {
+        &self.data
+    }
+}
+
+impl DerefMut for Game {
+    fn deref_mut(&mut self) -> &mut GameData {
+        &mut self.data
     }
 }
 
 #[account]
 pub struct PlayerInfo {
     pub owner: Pubkey,
-    pub games_won: u64,
-    pub games_drawn: u64,
-    pub games_lost: u64,
-
-    pub lifetime_wagering: u64,
-    pub lifetime_earnings: i64,
-
-    pub amount_in_games: u64,
+    pub stats: PlayerStats,
 }
 
 impl PlayerInfo {
@@ -659,10 +995,206 @@ impl PlayerInfo {
     }
 }
 
+impl Deref for PlayerInfo {
+    type Target = PlayerStats;
+
+    fn deref(&self) -> &PlayerStats {
+        &self.stats
+    }
+}
+
+impl DerefMut for PlayerInfo {
+    fn deref_mut(&mut self) -> &mut PlayerStats {
+        &mut self.stats
+    }
+}
+
+#[account]
+pub struct Config {
+    pub admin: Pubkey,
+
+    // can pause and unpause along with the admin
+    pub guardian: Pubkey,
+
+    // blocks creating and joining games, everything else keeps working
+    pub paused: bool,
+
+    // receives the protocol's share of fees
+    pub treasury: Pubkey,
+
+    // fee player 1 pays on top of the wager
+    pub fee_bps: u64,
+
+    // share of the fee that goes to the treasury, the rest goes to player 2
+    pub treasury_fee_bps: u64,
+
+    pub min_wager: u64,
+    pub max_wager: u64,
+
+    // v1 commitments aren't tied to a game, new games can only use them
+    // while this is set
+    pub allow_v1_commitments: bool,
+}
+
+impl Config {
+    pub fn space() -> usize {
+        // leaving some space for expansion
+        256
+    }
+    pub fn set(&mut self, params: ConfigParams) -> Result<()> {
+        if params.fee_bps > MAX_BPS
+            || params.treasury_fee_bps > MAX_BPS
+            || params.min_wager > params.max_wager
+        {
+            return err!(RpsError::InvalidConfig);
+        }
+        self.admin = params.admin;
+        self.guardian = params.guardian;
+        self.treasury = params.treasury;
+        self.fee_bps = params.fee_bps;
+        self.treasury_fee_bps = params.treasury_fee_bps;
+        self.min_wager = params.min_wager;
+        self.max_wager = params.max_wager;
+        self.allow_v1_commitments = params.allow_v1_commitments;
+        Ok(())
+    }
+    pub fn can_pause(&self, authority: Pubkey) -> bool {
+        authority == self.admin || authority == self.guardian
+    }
+    pub fn treasury_fee(&self, fee_amount: u64) -> Option<u64> {
+        let treasury_fee = (fee_amount as u128)
+            .checked_mul(self.treasury_fee_bps as u128)?
+            .checked_div(MAX_BPS as u128)?;
+        u64::try_from(treasury_fee).ok()
+    }
+}
+
 #[error_code]
 pub enum RpsError {
     #[msg("Bet too large")]
     BetTooLarge,
     #[msg("Math Overflow")]
     MathOverflow,
+    #[msg("Challenge expired")]
+    ChallengeExpired,
+    #[msg("Invalid entry secret")]
+    InvalidEntrySecret,
+    #[msg("Game not expired yet")]
+    NotExpired,
+    #[msg("Only player 1 can expire unmatched games")]
+    OnlyPlayer1CanExpireUnmatched,
+    #[msg("Only player 2 can expire unrevealed games")]
+    OnlyPlayer2CanExpireUnrevealed,
+    #[msg("Only player 1 can do this")]
+    NotPlayer1,
+    #[msg("Invalid commitment")]
+    InvalidCommitment,
+    #[msg("Invalid action for the current game state")]
+    InvalidTransition,
+    #[msg("Challenge or reveal duration out of bounds")]
+    InvalidExpiryDuration,
+    #[msg("Best of must be an odd number of rounds up to 7")]
+    InvalidBestOf,
+    #[msg("Round expired")]
+    RoundExpired,
+    #[msg("Only player 1 can expire games waiting on player 2 to play")]
+    OnlyPlayer1CanExpireUnplayed,
+    #[msg("Only player 2 can expire games waiting on player 1 to commit")]
+    OnlyPlayer2CanExpireUncommitted,
+    #[msg("Only player 2 can do this")]
+    NotPlayer2,
+    #[msg("Wrong join or play instruction for the game's commit mode")]
+    WrongCommitMode,
+    #[msg("Only player 1 can expire games player 2 didn't reveal")]
+    OnlyPlayer1CanExpireUnrevealed,
+    #[msg("Player is not in this game")]
+    NotInGame,
+    #[msg("Move isn't part of the game's move set")]
+    InvalidMove,
+    #[msg("Invalid config")]
+    InvalidConfig,
+    #[msg("Wager outside of the configured limits")]
+    WagerOutOfBounds,
+    #[msg("Game creation and joining are paused")]
+    Paused,
+    #[msg("Only the admin or guardian can pause")]
+    NotPauseAuthority,
+    #[msg("V1 commitments are disabled, commit with v2")]
+    V1CommitmentsDisabled,
+    #[msg("Only the program's upgrade authority can initialize the config")]
+    NotUpgradeAuthority,
+}
+
+impl From<TransitionError> for RpsError {
+    fn from(err: TransitionError) -> Self {
+        match err {
+            TransitionError::InvalidExpiryDuration => RpsError::InvalidExpiryDuration,
+            TransitionError::InvalidBestOf => RpsError::InvalidBestOf,
+            TransitionError::WrongCommitMode => RpsError::WrongCommitMode,
+            TransitionError::ChallengeExpired => RpsError::ChallengeExpired,
+            TransitionError::RoundExpired => RpsError::RoundExpired,
+            TransitionError::InvalidEntrySecret => RpsError::InvalidEntrySecret,
+            TransitionError::NotExpired => RpsError::NotExpired,
+            TransitionError::OnlyPlayer1CanExpireUnmatched => {
+                RpsError::OnlyPlayer1CanExpireUnmatched
+            }
+            TransitionError::OnlyPlayer1CanExpireUnrevealed => {
+                RpsError::OnlyPlayer1CanExpireUnrevealed
+            }
+            TransitionError::OnlyPlayer2CanExpireUnrevealed => {
+                RpsError::OnlyPlayer2CanExpireUnrevealed
+            }
+            TransitionError::OnlyPlayer1CanExpireUnplayed => RpsError::OnlyPlayer1CanExpireUnplayed,
+            TransitionError::OnlyPlayer2CanExpireUncommitted => {
+                RpsError::OnlyPlayer2CanExpireUncommitted
+            }
+            TransitionError::NotPlayer1 => RpsError::NotPlayer1,
+            TransitionError::NotPlayer2 => RpsError::NotPlayer2,
+            TransitionError::NotInGame => RpsError::NotInGame,
+            TransitionError::InvalidCommitment => RpsError::InvalidCommitment,
+            TransitionError::InvalidMove => RpsError::InvalidMove,
+            TransitionError::InvalidTransition => RpsError::InvalidTransition,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_error_codes() {
+        // clients match on these, new variants go at the end of the enum
+        let codes = [
+            (RpsError::BetTooLarge, 6000),
+            (RpsError::MathOverflow, 6001),
+            (RpsError::ChallengeExpired, 6002),
+            (RpsError::InvalidEntrySecret, 6003),
+            (RpsError::NotExpired, 6004),
+            (RpsError::OnlyPlayer1CanExpireUnmatched, 6005),
+            (RpsError::OnlyPlayer2CanExpireUnrevealed, 6006),
+            (RpsError::NotPlayer1, 6007),
+            (RpsError::InvalidCommitment, 6008),
+            (RpsError::InvalidTransition, 6009),
+            (RpsError::InvalidExpiryDuration, 6010),
+            (RpsError::InvalidBestOf, 6011),
+            (RpsError::RoundExpired, 6012),
+            (RpsError::OnlyPlayer1CanExpireUnplayed, 6013),
+            (RpsError::OnlyPlayer2CanExpireUncommitted, 6014),
+            (RpsError::NotPlayer2, 6015),
+            (RpsError::WrongCommitMode, 6016),
+            (RpsError::OnlyPlayer1CanExpireUnrevealed, 6017),
+            (RpsError::NotInGame, 6018),
+            (RpsError::InvalidMove, 6019),
+            (RpsError::InvalidConfig, 6020),
+            (RpsError::WagerOutOfBounds, 6021),
+            (RpsError::Paused, 6022),
+            (RpsError::NotPauseAuthority, 6023),
+            (RpsError::V1CommitmentsDisabled, 6024),
+            (RpsError::NotUpgradeAuthority, 6025),
+        ];
+        for (error, code) in codes {
+            assert_eq!(u32::from(error), code, "{}", error);
+        }
+    }
 }
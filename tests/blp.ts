@@ -0,0 +1,329 @@
+import * as anchor from "@coral-xyz/anchor";
+import { PublicKey } from "@solana/web3.js";
+import {
+  createAssociatedTokenAccount,
+  getAccount,
+  TOKEN_PROGRAM_ID,
+} from "@solana/spl-token";
+import { IDL as RPS_IDL } from "../target/types/rps";
+import { IDL as BLP_IDL } from "../target/types/blp";
+import { BN } from "bn.js";
+import { keccak_256 } from "js-sha3";
+import { randomBytes } from "crypto";
+import { expect } from "chai";
+
+describe("blp", () => {
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const provider = anchor.getProvider();
+  const rps = new anchor.Program(
+    RPS_IDL,
+    new PublicKey("rpsVN2ZC1K9hoGPs83xahjWo46cDNP49Tk7rQb56ipE"),
+    provider
+  );
+  const blp = new anchor.Program(
+    BLP_IDL,
+    new PublicKey("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS"),
+    provider
+  );
+
+  const LAMPORTS_PER_SOL = new BN(1000000000);
+  const WRAPPED_SOL_MINT = new PublicKey(
+    "So11111111111111111111111111111111111111112"
+  );
+
+  const pda = (seeds: Buffer[], programId: PublicKey) =>
+    PublicKey.findProgramAddressSync(seeds, programId)[0];
+  const utf8 = (s: string) => Buffer.from(anchor.utils.bytes.utf8.encode(s));
+
+  const airdrop = async (to: PublicKey, lamports: number) =>
+    provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(to, lamports)
+    );
+
+  it("Bot plays a game for the pool", async () => {
+    // rps.ts sets the config up when the whole suite runs, only create it
+    // when this file runs alone
+    const config = pda([utf8("config")], rps.programId);
+    if ((await rps.account.config.fetchNullable(config)) === null) {
+      const programData = pda(
+        [rps.programId.toBuffer()],
+        new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+      );
+      const treasury = anchor.web3.Keypair.generate();
+      await airdrop(treasury.publicKey, 1000000000);
+      await rps.methods
+        .initializeConfig({
+          admin: provider.publicKey,
+          guardian: provider.publicKey,
+          treasury: treasury.publicKey,
+          feeBps: new BN(350),
+          treasuryFeeBps: new BN(1000),
+          minWager: new BN(0),
+          maxWager: LAMPORTS_PER_SOL.mul(new BN(100)),
+          allowV1Commitments: false,
+        })
+        .accounts({
+          config,
+          payer: provider.publicKey,
+          program: rps.programId,
+          programData,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+    }
+
+    // the provider creates the pool and is its bot authority, the admin is a
+    // separate key
+    const admin = anchor.web3.Keypair.generate();
+    const poolSeed = new BN(randomBytes(8));
+    const pool = pda(
+      [utf8("pool"), poolSeed.toArrayLike(Buffer, "le", 8)],
+      blp.programId
+    );
+    const poolAuthority = pda([utf8("authority"), pool.toBuffer()], blp.programId);
+    const poolAuthorityPlayerInfo = pda(
+      [utf8("player_info"), poolAuthority.toBuffer()],
+      rps.programId
+    );
+    const lpTokenMint = pda([utf8("mint"), pool.toBuffer()], blp.programId);
+
+    const risk = {
+      maxWager: LAMPORTS_PER_SOL,
+      maxInGamesBps: new BN(5000),
+      maxOpenGames: new BN(4),
+      allowedMints: [WRAPPED_SOL_MINT],
+    };
+    await blp.methods
+      .createPool(poolSeed, new BN(1000), risk, admin.publicKey)
+      .accounts({
+        pool,
+        poolAuthority,
+        poolAuthorityPlayerInfo,
+        lpTokenMint,
+        botAuthority: provider.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        rpsProgram: rps.programId,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    const poolPlayerInfo = await rps.account.playerInfo.fetch(
+      poolAuthorityPlayerInfo
+    );
+    expect(poolPlayerInfo.owner.toBase58()).eq(poolAuthority.toBase58());
+
+    // the bot key can't do anything only the admin can
+    const adminCalls = [
+      blp.methods.updatePool(risk),
+      blp.methods.setBotAuthority(provider.publicKey),
+      blp.methods.revokeBot(),
+      blp.methods.transferAdmin(provider.publicKey),
+    ];
+    for (const call of adminCalls) {
+      try {
+        await call.accounts({ pool, admin: provider.publicKey }).rpc();
+        expect.fail("bot authority acted as admin");
+      } catch (err) {
+        expect(err.error.errorCode.code).eq("NotAdmin");
+      }
+    }
+    await blp.methods
+      .updatePool(risk)
+      .accounts({ pool, admin: admin.publicKey })
+      .signers([admin])
+      .rpc();
+
+    // fund the pool, deposits are queued and turned into lp once the pool's
+    // games have settled
+    const depositor = anchor.web3.Keypair.generate();
+    await airdrop(depositor.publicKey, 1000000000 * 10);
+    const depositorLp = await createAssociatedTokenAccount(
+      provider.connection,
+      depositor,
+      lpTokenMint,
+      depositor.publicKey
+    );
+    const deposit = async (amount: BN, minLpOut = new BN(1)) => {
+      const epoch = (await blp.account.pool.fetch(pool)).epoch;
+      const depositRequest = pda(
+        [
+          utf8("deposit"),
+          pool.toBuffer(),
+          depositor.publicKey.toBuffer(),
+          epoch.toArrayLike(Buffer, "le", 8),
+        ],
+        blp.programId
+      );
+      await blp.methods
+        .deposit(amount, minLpOut)
+        .accounts({
+          pool,
+          poolAuthority,
+          depositRequest,
+          userAuthority: depositor.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([depositor])
+        .rpc();
+      return depositRequest;
+    };
+    // anyone can process a deposit
+    const processDeposit = (depositRequest: PublicKey) =>
+      blp.methods
+        .processDeposit()
+        .accounts({
+          pool,
+          poolAuthority,
+          poolAuthorityPlayerInfo,
+          lpTokenMint,
+          depositRequest,
+          owner: depositor.publicKey,
+          userLpTokenAccount: depositorLp,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+
+    await processDeposit(await deposit(LAMPORTS_PER_SOL.mul(new BN(2))));
+    expect(
+      (await getAccount(provider.connection, depositorLp)).amount.toString()
+    ).eq("2000000000");
+
+    // a player opens a game for the bot to join
+    const player = anchor.web3.Keypair.generate();
+    await airdrop(player.publicKey, 1000000000 * 10);
+    const playerInfo = pda(
+      [utf8("player_info"), player.publicKey.toBuffer()],
+      rps.programId
+    );
+    await rps.methods
+      .createPlayerInfo()
+      .accounts({
+        playerInfo,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        owner: player.publicKey,
+      })
+      .signers([player])
+      .rpc();
+
+    const gameSeed = new BN(randomBytes(8));
+    const game = pda(
+      [utf8("game"), gameSeed.toArrayLike(Buffer, "le", 8)],
+      rps.programId
+    );
+    const gameAuthority = pda([utf8("authority"), game.toBuffer()], rps.programId);
+    const wagerAmount = new BN(1000000);
+    const salt = randomBytes(32);
+    const commitment = Buffer.from(
+      keccak_256(
+        Buffer.concat([
+          Buffer.from("rps-commitment-v2"),
+          rps.programId.toBuffer(),
+          game.toBuffer(),
+          player.publicKey.toBuffer(),
+          salt,
+          new BN(1).toArrayLike(Buffer, "le", 1),
+        ])
+      ),
+      "hex"
+    );
+    await rps.methods
+      .createGame(gameSeed, commitment.toJSON().data, wagerAmount, {
+        entryProof: null,
+        challengeDuration: new BN(600),
+        revealDuration: new BN(600),
+        bestOf: 1,
+        commitMode: { player1: {} },
+        moveSet: { classic: {} },
+        commitmentVersion: { v2: {} },
+      })
+      .accounts({
+        game,
+        config,
+        player: player.publicKey,
+        playerInfo,
+        gameAuthority,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([player])
+      .rpc();
+
+    await blp.methods
+      .botPlay({ rock: {} })
+      .accounts({
+        pool,
+        poolAuthority,
+        poolAuthorityPlayerInfo,
+        lpTokenMint,
+        game,
+        rpsConfig: config,
+        gameAuthority,
+        botAuthority: provider.publicKey,
+        rpsProgram: rps.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    const poolPlayerInfoInGame = await rps.account.playerInfo.fetch(
+      poolAuthorityPlayerInfo
+    );
+    expect(poolPlayerInfoInGame.stats.amountInGames.toString()).eq(
+      wagerAmount.toString()
+    );
+    const gameAccount = await rps.account.game.fetch(game);
+    expect(JSON.stringify(gameAccount.state)).contains(
+      poolAuthority.toBase58()
+    );
+
+    // the player knows they won once the bot has moved, a deposit now is
+    // only turned into lp after the game settles
+    const depositRequest = await deposit(LAMPORTS_PER_SOL);
+    try {
+      await processDeposit(depositRequest);
+      expect.fail("deposit was processed with a game open");
+    } catch (err) {
+      expect(err.error.errorCode.code).eq("GamesOpen");
+    }
+
+    await rps.methods
+      .revealGame({ paper: {} }, { v2: [[...salt]] })
+      .accounts({
+        player: player.publicKey,
+        playerInfo,
+        game,
+      })
+      .signers([player])
+      .rpc();
+    const { treasury } = await rps.account.config.fetch(config);
+    await rps.methods
+      .settleGame()
+      .accounts({
+        game,
+        player1: player.publicKey,
+        player2: poolAuthority,
+        player1Info: playerInfo,
+        player2Info: poolAuthorityPlayerInfo,
+        gameAuthority,
+        config,
+        treasury,
+      })
+      .rpc();
+
+    // the pool lost the wager, the deposit gets lp at the price after that
+    await processDeposit(depositRequest);
+    const lp = (await getAccount(provider.connection, depositorLp)).amount;
+    expect(lp > BigInt(3000000000)).to.be.true;
+
+    // a deposit that comes to less lp than asked for goes back instead
+    const refunded = await deposit(LAMPORTS_PER_SOL, LAMPORTS_PER_SOL.mul(new BN(2)));
+    const before = await provider.connection.getBalance(depositor.publicKey);
+    await processDeposit(refunded);
+    expect(
+      (await getAccount(provider.connection, depositorLp)).amount
+    ).eq(lp);
+    expect(
+      (await provider.connection.getBalance(depositor.publicKey)) - before
+    ).gte(1000000000);
+  });
+});
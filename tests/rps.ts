@@ -9,6 +9,7 @@ import {
 import { Rps, IDL } from "../target/types/rps";
 import { BN } from "bn.js";
 import { keccak_256 } from "js-sha3";
+import { randomBytes } from "crypto";
 import { expect, should } from "chai";
 
 describe("rps", () => {
@@ -38,7 +39,7 @@ describe("rps", () => {
       ],
       program.programId
     );
-    const salt = Math.floor(Math.random() * 10000000);
+    const salt = randomBytes(32);
     const player1Choice = 1;
 
     await provider.connection.confirmTransaction(
@@ -48,6 +49,41 @@ describe("rps", () => {
       )
     );
 
+
+    const [config, _configBump] = PublicKey.findProgramAddressSync(
+      [Buffer.from(anchor.utils.bytes.utf8.encode("config"))],
+      program.programId
+    );
+    // only the upgrade authority, the provider here, can initialize the config
+    const [programData, _programDataBump] = PublicKey.findProgramAddressSync(
+      [program.programId.toBuffer()],
+      new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+    );
+    const treasury = anchor.web3.Keypair.generate();
+    // treasury needs to be rent exempt to receive small fee transfers
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(treasury.publicKey, 1000000000)
+    );
+    await program.methods
+      .initializeConfig({
+        admin: provider.publicKey,
+        guardian: provider.publicKey,
+        treasury: treasury.publicKey,
+        feeBps: new BN(350),
+        treasuryFeeBps: new BN(1000),
+        minWager: new BN(0),
+        maxWager: LAMPORTS_PER_SOL.mul(new BN(100)),
+        allowV1Commitments: false,
+      })
+      .accounts({
+        config,
+        payer: provider.publicKey,
+        program: program.programId,
+        programData,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+
     const [playerInfo, _playerInfoBump] = PublicKey.findProgramAddressSync(
       [
         Buffer.from(anchor.utils.bytes.utf8.encode("player_info")),
@@ -76,16 +112,33 @@ describe("rps", () => {
       );
 
     const buf = Buffer.concat([
+      Buffer.from("rps-commitment-v2"),
+      program.programId.toBuffer(),
+      game.toBuffer(),
       player.publicKey.toBuffer(),
-      new anchor.BN(salt).toArrayLike(Buffer, "le", 8),
+      salt,
       new anchor.BN(player1Choice).toArrayLike(Buffer, "le", 1),
     ]);
     let commitment = Buffer.from(keccak_256(buf), "hex");
 
     const tx = await program.methods
-      .createGame(gameSeed, commitment.toJSON().data, wagerAmount, null)
+      .createGame(
+        gameSeed,
+        commitment.toJSON().data,
+        wagerAmount,
+        {
+          entryProof: null,
+          challengeDuration: new BN(600),
+          revealDuration: new BN(600),
+          bestOf: 1,
+          commitMode: { player1: {} },
+          moveSet: { classic: {} },
+          commitmentVersion: { v2: {} },
+        }
+      )
       .accounts({
         game: game,
+        config,
         player: player.publicKey,
         playerInfo: playerInfo,
         gameAuthority: gameAuthority,
@@ -97,7 +150,7 @@ describe("rps", () => {
     console.log("Your transaction signature", tx);
     const player1InfoAccountGameCreated =
       await program.account.playerInfo.fetch(playerInfo);
-    expect(player1InfoAccountGameCreated.amountInGames.toString()).eq(
+    expect(player1InfoAccountGameCreated.stats.amountInGames.toString()).eq(
       "1000000"
     );
 
@@ -135,6 +188,7 @@ describe("rps", () => {
         player: player2.publicKey,
         playerInfo: player2Info,
         game: game,
+        config,
         gameAuthority: gameAuthority,
       })
       .signers([player2])
@@ -145,7 +199,7 @@ describe("rps", () => {
     // console.log("Your game account", gameAccount2);
 
     const tx3 = await program.methods
-      .revealGame({ paper: {} }, new BN(salt))
+      .revealGame({ paper: {} }, { v2: [[...salt]] })
       .accounts({
         player: player.publicKey,
         playerInfo,
@@ -166,6 +220,8 @@ describe("rps", () => {
         player1Info: playerInfo,
         player2Info: player2Info,
         gameAuthority: gameAuthority,
+        config,
+        treasury: treasury.publicKey,
       })
       .rpc({ skipPreflight: true });
 
@@ -9,6 +9,7 @@ import {
 import { RpsToken, IDL } from "../target/types/rps_token";
 import { BN } from "bn.js";
 import { keccak_256 } from "js-sha3";
+import { randomBytes } from "crypto";
 import { expect, should } from "chai";
 import {
   TOKEN_PROGRAM_ID,
@@ -41,7 +42,7 @@ describe("rps_token", () => {
     const player = anchor.web3.Keypair.generate();
     const gameSeed = new BN(4);
     const wagerAmount = new BN(1000000);
-    const salt = Math.floor(Math.random() * 10000000);
+    const salt = randomBytes(32);
     const player1Choice = 1;
 
     await provider.connection.confirmTransaction(
@@ -75,6 +76,77 @@ describe("rps_token", () => {
       100000000000
     );
 
+
+    const [config, _configBump] = PublicKey.findProgramAddressSync(
+      [Buffer.from(anchor.utils.bytes.utf8.encode("config"))],
+      program.programId
+    );
+    // only the upgrade authority, the provider here, can initialize the config
+    const [programData, _programDataBump] = PublicKey.findProgramAddressSync(
+      [program.programId.toBuffer()],
+      new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+    );
+    const treasury = anchor.web3.Keypair.generate();
+    const treasuryTokenAccount = await createAssociatedTokenAccount(
+      provider.connection,
+      player,
+      mint,
+      treasury.publicKey
+    );
+    await program.methods
+      .initializeConfig({
+        admin: provider.publicKey,
+        guardian: provider.publicKey,
+        treasury: treasury.publicKey,
+        feeBps: new BN(350),
+        treasuryFeeBps: new BN(1000),
+        minWager: new BN(0),
+        maxWager: LAMPORTS_PER_SOL.mul(new BN(100)),
+        allowV1Commitments: false,
+      })
+      .accounts({
+        config,
+        payer: provider.publicKey,
+        program: program.programId,
+        programData,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    const [mintConfig, _mintConfigBump] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from(anchor.utils.bytes.utf8.encode("mint_config")),
+        mint.toBuffer(),
+      ],
+      program.programId
+    );
+    await program.methods
+      .addMint(
+        {
+          enabled: true,
+          minWager: new BN(0),
+          maxWager: LAMPORTS_PER_SOL.mul(new BN(100)),
+          feeBps: null,
+        },
+        false
+      )
+      .accounts({
+        config,
+        admin: provider.publicKey,
+        mint,
+        mintConfig,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    const [pausedMint, _pausedMintBump] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from(anchor.utils.bytes.utf8.encode("paused_mint")),
+        mint.toBuffer(),
+      ],
+      program.programId
+    );
+
     const [playerInfo, _playerInfoBump] = PublicKey.findProgramAddressSync(
       [
         Buffer.from(anchor.utils.bytes.utf8.encode("player_info")),
@@ -126,18 +198,37 @@ describe("rps_token", () => {
       );
 
     const buf = Buffer.concat([
+      Buffer.from("rps-commitment-v2"),
+      program.programId.toBuffer(),
+      game.toBuffer(),
       player.publicKey.toBuffer(),
-      new anchor.BN(salt).toArrayLike(Buffer, "le", 8),
+      salt,
       new anchor.BN(player1Choice).toArrayLike(Buffer, "le", 1),
     ]);
     let commitment = Buffer.from(keccak_256(buf), "hex");
 
     const tx = await program.methods
-      .createGame(gameSeed, commitment.toJSON().data, wagerAmount, null)
+      .createGame(
+        gameSeed,
+        commitment.toJSON().data,
+        wagerAmount,
+        {
+          entryProof: null,
+          challengeDuration: new BN(600),
+          revealDuration: new BN(600),
+          bestOf: 1,
+          commitMode: { player1: {} },
+          moveSet: { classic: {} },
+          commitmentVersion: { v2: {} },
+        }
+      )
       .accounts({
         game: game,
+        config,
         player: player.publicKey,
         mint: mint,
+        mintConfig,
+        pausedMint,
         playerTokenAccount: playerTokenAccount,
         playerInfo: playerInfo,
         gameAuthority: gameAuthority,
@@ -153,7 +244,7 @@ describe("rps_token", () => {
 
     const player1InfoAccountGameCreated =
       await program.account.playerInfo.fetch(playerInfo);
-    expect(player1InfoAccountGameCreated.amountInGames.toString()).eq(
+    expect(player1InfoAccountGameCreated.stats.amountInGames.toString()).eq(
       "1000000"
     );
 
@@ -209,10 +300,13 @@ describe("rps_token", () => {
         playerInfo: player2Info,
         systemProgram: anchor.web3.SystemProgram.programId,
         game,
+        config,
+        pausedMint,
         player: player2.publicKey,
         playerTokenAccount: player2TokenAccount,
         gameAuthority: gameAuthority,
         escrowTokenAccount,
+        mint,
         tokenProgram: TOKEN_PROGRAM_ID,
       })
       .signers([player2])
@@ -223,7 +317,7 @@ describe("rps_token", () => {
     // console.log("Your game account", gameAccount2);
 
     const tx3 = await program.methods
-      .revealGame({ paper: {} }, new BN(salt))
+      .revealGame({ paper: {} }, { v2: [[...salt]] })
       .accounts({
         player: player.publicKey,
         playerInfo,
@@ -243,6 +337,7 @@ describe("rps_token", () => {
         game: game,
         gameAuthority: gameAuthority,
         escrowTokenAccount,
+        mint,
         tokenProgram: TOKEN_PROGRAM_ID,
         player1: player.publicKey,
         player2: player2.publicKey,
@@ -250,6 +345,8 @@ describe("rps_token", () => {
         player2Info: player2Info,
         player1TokenAccount: playerTokenAccount,
         player2TokenAccount,
+        config,
+        treasuryTokenAccount,
       })
       .rpc({ skipPreflight: true });
     console.log("Your transaction signature settlegame", tx4);
@@ -264,6 +361,7 @@ describe("rps_token", () => {
         game,
         gameAuthority: gameAuthority,
         escrowTokenAccount,
+        mint,
         tokenProgram: TOKEN_PROGRAM_ID,
         player1: player.publicKey,
         rpsProgram: program.programId,